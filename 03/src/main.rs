@@ -1,3 +1,126 @@
-fn main() {
-    println!("Hello, world!");
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+
+type Point = (i64, i64);
+
+struct Segment {
+    start: Point,
+    end: Point,
+    // steps taken along the wire before reaching start
+    steps: i64,
+}
+
+fn distance(a: Point, b: Point) -> i64 {
+    (a.0 - b.0).abs() + (a.1 - b.1).abs()
+}
+
+fn parse_wire(line: &str) -> Result<Vec<Segment>> {
+    let mut segments = vec![];
+    let mut position = (0, 0);
+    let mut steps = 0;
+    for movement in line.trim().split(',') {
+        let invalid = || {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid movement {:?}", movement),
+            )
+        };
+        let length: i64 = movement
+            .get(1..)
+            .and_then(|length| length.parse().ok())
+            .ok_or_else(invalid)?;
+        let end = match movement.chars().next() {
+            Some('R') => (position.0 + length, position.1),
+            Some('L') => (position.0 - length, position.1),
+            Some('U') => (position.0, position.1 + length),
+            Some('D') => (position.0, position.1 - length),
+            _ => return Err(invalid()),
+        };
+        segments.push(Segment {
+            start: position,
+            end,
+            steps,
+        });
+        position = end;
+        steps += length;
+    }
+    Ok(segments)
+}
+
+// points where the two segments meet, as the corners of their overlap; segments are axis
+// aligned so the overlap is a single point or a stretch of one line
+fn overlap(a: &Segment, b: &Segment) -> Option<(Point, Point)> {
+    let low = (
+        a.start.0.min(a.end.0).max(b.start.0.min(b.end.0)),
+        a.start.1.min(a.end.1).max(b.start.1.min(b.end.1)),
+    );
+    let high = (
+        a.start.0.max(a.end.0).min(b.start.0.max(b.end.0)),
+        a.start.1.max(a.end.1).min(b.start.1.max(b.end.1)),
+    );
+    if low.0 > high.0 || low.1 > high.1 {
+        return None;
+    }
+    Some((low, high))
+}
+
+// smallest (distance to origin, combined steps) over every crossing, each minimised separately
+fn closest_intersections(first: &[Segment], second: &[Segment]) -> Option<(i64, i64)> {
+    let mut closest: Option<(i64, i64)> = None;
+    for a in first {
+        for b in second {
+            let Some((low, high)) = overlap(a, b) else {
+                continue;
+            };
+            let clamp = |p: Point| (p.0.clamp(low.0, high.0), p.1.clamp(low.1, high.1));
+            // both measures are piecewise linear along the overlap, so their minimums lie at
+            // its ends or where they bend, with the neighbours of the origin standing in for it
+            let candidates = [
+                low,
+                high,
+                clamp((0, 0)),
+                clamp(a.start),
+                clamp(b.start),
+                clamp((1, 0)),
+                clamp((-1, 0)),
+                clamp((0, 1)),
+                clamp((0, -1)),
+            ];
+            for point in candidates {
+                if point == (0, 0) {
+                    continue;
+                }
+                let manhattan = distance(point, (0, 0));
+                let steps = a.steps + distance(a.start, point) + b.steps + distance(b.start, point);
+                closest = Some(match closest {
+                    Some((m, s)) => (m.min(manhattan), s.min(steps)),
+                    None => (manhattan, steps),
+                });
+            }
+        }
+    }
+    closest
+}
+
+fn main() -> Result<()> {
+    let input = fs::read_to_string("input.txt")?;
+    let wires: Vec<Vec<Segment>> = input
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(parse_wire)
+        .collect::<Result<_>>()?;
+    if wires.len() != 2 {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("expected 2 wires, found {}", wires.len()),
+        ));
+    }
+
+    let (part_1_answer, part_2_answer) = closest_intersections(&wires[0], &wires[1])
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "wires never cross"))?;
+
+    println!("Part 1: {:?}", part_1_answer);
+    println!("Part 2: {:?}", part_2_answer);
+
+    Ok(())
 }
@@ -1,3 +1,141 @@
-fn main() {
-    println!("Hello, world!");
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+
+// what a password has to satisfy; a run is a maximal group of equal adjacent digits
+#[derive(Clone, Copy)]
+struct Rules {
+    non_decreasing: bool,
+    // some run must have between min_run and max_run digits, no requirement if min_run is 0
+    min_run: usize,
+    max_run: usize,
+}
+
+impl Rules {
+    fn part_1() -> Rules {
+        Rules {
+            non_decreasing: true,
+            min_run: 2,
+            max_run: usize::MAX,
+        }
+    }
+
+    fn part_2() -> Rules {
+        Rules {
+            non_decreasing: true,
+            min_run: 2,
+            max_run: 2,
+        }
+    }
+
+    // run lengths past this all behave the same
+    fn run_cap(&self) -> usize {
+        if self.max_run == usize::MAX {
+            self.min_run
+        } else {
+            self.max_run + 1
+        }
+    }
+
+    fn run_matches(&self, run: usize) -> bool {
+        run >= self.min_run && run <= self.max_run
+    }
+}
+
+// digit dynamic program over numbers from 0 up to a limit, walking digits from the most
+// significant; only states that are no longer bound by the limit are memoised
+struct Counter {
+    rules: Rules,
+    digits: Vec<u8>,
+    // (position, last digit, run length, run requirement met, started) -> count
+    memo: HashMap<(usize, u8, usize, bool, bool), u128>,
+}
+
+impl Counter {
+    fn count(
+        &mut self,
+        position: usize,
+        tight: bool,
+        started: bool,
+        last: u8,
+        run: usize,
+        satisfied: bool,
+    ) -> u128 {
+        if position == self.digits.len() {
+            let satisfied = satisfied || self.rules.run_matches(run) || self.rules.min_run == 0;
+            return (started && satisfied) as u128;
+        }
+        let key = (position, last, run, satisfied, started);
+        if !tight {
+            if let Some(count) = self.memo.get(&key) {
+                return *count;
+            }
+        }
+
+        let max_digit = if tight { self.digits[position] } else { 9 };
+        let mut count = 0;
+        for digit in 0..=max_digit {
+            let tight = tight && digit == max_digit;
+            count += if !started && digit == 0 {
+                // leading zero, the number hasn't started yet
+                self.count(position + 1, tight, false, 0, 0, false)
+            } else if started && self.rules.non_decreasing && digit < last {
+                0
+            } else if started && digit == last {
+                let run = (run + 1).min(self.rules.run_cap());
+                self.count(position + 1, tight, true, digit, run, satisfied)
+            } else {
+                let satisfied = satisfied || (started && self.rules.run_matches(run));
+                self.count(position + 1, tight, true, digit, 1, satisfied)
+            };
+        }
+
+        if !tight {
+            self.memo.insert(key, count);
+        }
+        count
+    }
+}
+
+// number of passwords from 1 to limit
+fn count_up_to(limit: u128, rules: Rules) -> u128 {
+    let mut counter = Counter {
+        rules,
+        digits: limit.to_string().bytes().map(|b| b - b'0').collect(),
+        memo: HashMap::new(),
+    };
+    counter.count(0, true, false, 0, 0, false)
+}
+
+fn count_in_range(low: u128, high: u128, rules: Rules) -> u128 {
+    if low > high {
+        return 0;
+    }
+    count_up_to(high, rules) - count_up_to(low.saturating_sub(1), rules)
+}
+
+fn parse() -> Result<(u128, u128)> {
+    let input = fs::read_to_string("input.txt")?;
+    input
+        .trim()
+        .split_once('-')
+        .and_then(|(low, high)| Some((low.trim().parse().ok()?, high.trim().parse().ok()?)))
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "expected a range like 123456-654321, got {:?}",
+                    input.trim()
+                ),
+            )
+        })
+}
+
+fn main() -> Result<()> {
+    let (low, high) = parse()?;
+
+    println!("Part 1: {:?}", count_in_range(low, high, Rules::part_1()));
+    println!("Part 2: {:?}", count_in_range(low, high, Rules::part_2()));
+
+    Ok(())
 }
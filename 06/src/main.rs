@@ -1,3 +1,204 @@
-fn main() {
-    println!("Hello, world!");
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+
+fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+struct OrbitMap {
+    names: Vec<String>,
+    index: HashMap<String, usize>,
+    parent: Vec<Option<usize>>,
+    depth: Vec<usize>,
+    // ancestors[k][body] is the body 2^k levels up, for answering ancestor queries in log time
+    ancestors: Vec<Vec<Option<usize>>>,
+}
+
+impl OrbitMap {
+    fn parse(input: &str) -> Result<OrbitMap> {
+        let mut map = OrbitMap {
+            names: vec![],
+            index: HashMap::new(),
+            parent: vec![],
+            depth: vec![],
+            ancestors: vec![],
+        };
+        for (i, line) in input.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (centre, satellite) = line
+                .split_once(')')
+                .ok_or_else(|| invalid(format!("line {}: expected A)B, got {:?}", i + 1, line)))?;
+            let centre = map.body(centre);
+            let satellite = map.body(satellite);
+            if let Some(existing) = map.parent[satellite] {
+                return Err(invalid(format!(
+                    "line {}: {} orbits both {} and {}",
+                    i + 1,
+                    map.names[satellite],
+                    map.names[existing],
+                    map.names[centre]
+                )));
+            }
+            map.parent[satellite] = Some(centre);
+        }
+        map.compute_depths()?;
+        map.compute_ancestors();
+        Ok(map)
+    }
+
+    fn body(&mut self, name: &str) -> usize {
+        if let Some(index) = self.index.get(name) {
+            return *index;
+        }
+        self.names.push(name.to_string());
+        self.parent.push(None);
+        self.index.insert(name.to_string(), self.names.len() - 1);
+        self.names.len() - 1
+    }
+
+    fn compute_depths(&mut self) -> Result<()> {
+        let mut depth: Vec<Option<usize>> = vec![None; self.names.len()];
+        for body in 0..self.names.len() {
+            // walk up to a body of known depth, then fill in the path on the way back
+            let mut path = vec![];
+            let mut current = body;
+            let mut base = loop {
+                if let Some(known) = depth[current] {
+                    break known;
+                }
+                if path.contains(&current) {
+                    let mut cycle: Vec<&str> =
+                        path.iter().map(|b| self.names[*b].as_str()).collect();
+                    cycle.push(&self.names[current]);
+                    return Err(invalid(format!(
+                        "orbits form a cycle: {}",
+                        cycle.join(" orbits ")
+                    )));
+                }
+                path.push(current);
+                match self.parent[current] {
+                    Some(parent) => current = parent,
+                    None => {
+                        path.pop();
+                        depth[current] = Some(0);
+                        break 0;
+                    }
+                }
+            };
+            for body in path.into_iter().rev() {
+                base += 1;
+                depth[body] = Some(base);
+            }
+        }
+        self.depth = depth.into_iter().map(|d| d.unwrap()).collect();
+        Ok(())
+    }
+
+    fn compute_ancestors(&mut self) {
+        self.ancestors = vec![self.parent.clone()];
+        let max_depth = self.depth.iter().copied().max().unwrap_or(0);
+        while 1 << self.ancestors.len() <= max_depth {
+            let previous = self.ancestors.last().unwrap();
+            let next = previous
+                .iter()
+                .map(|ancestor| ancestor.and_then(|a| previous[a]))
+                .collect();
+            self.ancestors.push(next);
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Result<usize> {
+        self.index
+            .get(name)
+            .copied()
+            .ok_or_else(|| invalid(format!("no body named {}", name)))
+    }
+
+    // direct and indirect orbits of every body
+    fn total_orbits(&self) -> usize {
+        self.depth.iter().sum()
+    }
+
+    fn ancestor_at_depth(&self, mut body: usize, depth: usize) -> usize {
+        let mut levels = self.depth[body] - depth;
+        let mut k = 0;
+        while levels > 0 {
+            if levels & 1 == 1 {
+                body = self.ancestors[k][body].unwrap();
+            }
+            levels >>= 1;
+            k += 1;
+        }
+        body
+    }
+
+    // the deepest body both a and b orbit, directly or not (a body counts as its own
+    // ancestor); None if they are in separate systems
+    fn lowest_common_ancestor(&self, a: &str, b: &str) -> Result<Option<&str>> {
+        let (a, b) = (self.lookup(a)?, self.lookup(b)?);
+        Ok(self.lca(a, b).map(|body| self.names[body].as_str()))
+    }
+
+    fn lca(&self, a: usize, b: usize) -> Option<usize> {
+        let depth = self.depth[a].min(self.depth[b]);
+        let (mut a, mut b) = (
+            self.ancestor_at_depth(a, depth),
+            self.ancestor_at_depth(b, depth),
+        );
+        if a == b {
+            return Some(a);
+        }
+        for k in (0..self.ancestors.len()).rev() {
+            if let (Some(x), Some(y)) = (self.ancestors[k][a], self.ancestors[k][b]) {
+                if x != y {
+                    a = x;
+                    b = y;
+                }
+            }
+        }
+        match (self.parent[a], self.parent[b]) {
+            (Some(x), Some(y)) if x == y => Some(x),
+            _ => None,
+        }
+    }
+
+    // number of orbit hops between two bodies
+    fn distance(&self, a: &str, b: &str) -> Result<usize> {
+        let (a, b) = (self.lookup(a)?, self.lookup(b)?);
+        let ancestor = self.lca(a, b).ok_or_else(|| {
+            invalid(format!(
+                "{} and {} aren't in the same system",
+                self.names[a], self.names[b]
+            ))
+        })?;
+        Ok(self.depth[a] + self.depth[b] - 2 * self.depth[ancestor])
+    }
+
+    // transfers needed to move from the body a orbits to the body b orbits
+    fn transfers(&self, a: &str, b: &str) -> Result<usize> {
+        let orbited = |name: &str| -> Result<&str> {
+            let body = self.lookup(name)?;
+            self.parent[body]
+                .map(|parent| self.names[parent].as_str())
+                .ok_or_else(|| invalid(format!("{} doesn't orbit anything", name)))
+        };
+        self.distance(orbited(a)?, orbited(b)?)
+    }
+}
+
+fn main() -> Result<()> {
+    let map = OrbitMap::parse(&fs::read_to_string("input.txt")?)?;
+
+    println!("Part 1: {:?}", map.total_orbits());
+    println!("Part 2: {:?}", map.transfers("YOU", "SAN")?);
+
+    if let Some(ancestor) = map.lowest_common_ancestor("YOU", "SAN")? {
+        println!("YOU and SAN both orbit {}", ancestor);
+    }
+
+    Ok(())
 }
@@ -1,3 +1,210 @@
-fn main() {
-    println!("Hello, world!");
+use std::env;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+const BLACK: u8 = 0;
+const WHITE: u8 = 1;
+const TRANSPARENT: u8 = 2;
+
+// pixels are scaled up by this much in the written image files
+const SCALE: usize = 8;
+
+struct Image {
+    width: usize,
+    height: usize,
+    layers: Vec<Vec<u8>>,
+}
+
+fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+impl Image {
+    fn parse(data: &str, width: usize, height: usize) -> Result<Image> {
+        let pixels: Vec<u8> = data
+            .trim()
+            .chars()
+            .map(|c| match c.to_digit(10) {
+                Some(digit) => Ok(digit as u8),
+                None => Err(invalid(format!("invalid pixel {:?}", c))),
+            })
+            .collect::<Result<_>>()?;
+        let layer_size = width * height;
+        if layer_size == 0 || pixels.is_empty() || !pixels.len().is_multiple_of(layer_size) {
+            return Err(invalid(format!(
+                "{} pixels don't make whole {}x{} layers",
+                pixels.len(),
+                width,
+                height
+            )));
+        }
+        Ok(Image {
+            width,
+            height,
+            layers: pixels.chunks(layer_size).map(|l| l.to_vec()).collect(),
+        })
+    }
+
+    // ones times twos on the layer with the fewest zeros
+    fn checksum(&self) -> usize {
+        let count = |layer: &[u8], digit: u8| layer.iter().filter(|p| **p == digit).count();
+        let layer = self
+            .layers
+            .iter()
+            .min_by_key(|layer| count(layer, 0))
+            .unwrap();
+        count(layer, 1) * count(layer, 2)
+    }
+
+    // the first non-transparent pixel of each position, front layer first
+    fn compose(&self) -> Vec<u8> {
+        (0..self.width * self.height)
+            .map(|i| {
+                self.layers
+                    .iter()
+                    .map(|layer| layer[i])
+                    .find(|pixel| *pixel != TRANSPARENT)
+                    .unwrap_or(TRANSPARENT)
+            })
+            .collect()
+    }
+
+    fn render(&self, pixels: &[u8]) -> String {
+        let mut rendered = String::new();
+        for row in pixels.chunks(self.width) {
+            for pixel in row {
+                rendered.push(match *pixel {
+                    WHITE => '#',
+                    BLACK => ' ',
+                    _ => '?',
+                });
+            }
+            rendered.push('\n');
+        }
+        rendered
+    }
+
+    // plain bitmap, white pixels as 0 and everything else as 1 (ink)
+    fn pbm(&self, pixels: &[u8]) -> String {
+        let mut image = format!("P1\n{} {}\n", self.width * SCALE, self.height * SCALE);
+        for row in pixels.chunks(self.width) {
+            let line: Vec<&str> = row
+                .iter()
+                .flat_map(|pixel| [if *pixel == WHITE { "0" } else { "1" }; SCALE])
+                .collect();
+            for _ in 0..SCALE {
+                image += &line.join(" ");
+                image.push('\n');
+            }
+        }
+        image
+    }
+
+    // colour image, keeping pixels that stayed transparent visible in red
+    fn ppm(&self, pixels: &[u8]) -> Vec<u8> {
+        let mut image =
+            format!("P6\n{} {}\n255\n", self.width * SCALE, self.height * SCALE).into_bytes();
+        for row in pixels.chunks(self.width) {
+            for _ in 0..SCALE {
+                for pixel in row {
+                    let colour = match *pixel {
+                        WHITE => [255, 255, 255],
+                        BLACK => [0, 0, 0],
+                        _ => [255, 0, 0],
+                    };
+                    for _ in 0..SCALE {
+                        image.extend(colour);
+                    }
+                }
+            }
+        }
+        image
+    }
+}
+
+const USAGE: &str = "usage: aoc19_08 [WIDTH HEIGHT] [--export IMAGE.pbm|IMAGE.ppm]";
+
+// image size, defaulting to the puzzle's 25x6, and where to write the decoded image if anywhere
+struct Options {
+    width: usize,
+    height: usize,
+    export: Option<(PathBuf, Format)>,
+}
+
+enum Format {
+    Pbm,
+    Ppm,
+}
+
+// the format follows the extension
+fn export_format(path: &Path) -> Result<Format> {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("pbm") => Ok(Format::Pbm),
+        Some("ppm") => Ok(Format::Ppm),
+        _ => Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("{} should end in .pbm or .ppm", path.display()),
+        )),
+    }
+}
+
+fn parse_args() -> Result<Options> {
+    let usage = || Error::new(ErrorKind::InvalidInput, USAGE);
+    let mut export = None;
+    let mut size = vec![];
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--export" => {
+                let path = PathBuf::from(args.next().ok_or_else(usage)?);
+                let format = export_format(&path)?;
+                export = Some((path, format));
+            }
+            _ => size.push(arg),
+        }
+    }
+    let (width, height) = match size.as_slice() {
+        [] => (25, 6),
+        [width, height] => match (width.parse(), height.parse()) {
+            (Ok(width), Ok(height)) => (width, height),
+            _ => return Err(invalid(format!("invalid size {}x{}", width, height))),
+        },
+        _ => return Err(usage()),
+    };
+    Ok(Options {
+        width,
+        height,
+        export,
+    })
+}
+
+fn main() -> Result<()> {
+    let options = parse_args()?;
+    let (width, height) = (options.width, options.height);
+    let image = Image::parse(&fs::read_to_string("input.txt")?, width, height)?;
+
+    println!("Part 1: {:?}", image.checksum());
+
+    let composed = image.compose();
+    let grid: Vec<Vec<bool>> = composed
+        .chunks(width)
+        .map(|row| row.iter().map(|pixel| *pixel == WHITE).collect())
+        .collect();
+    match ocr::recognise(&grid) {
+        Ok(letters) => println!("Part 2: {}", letters),
+        Err(error) => {
+            eprintln!("{}", error);
+            println!("Part 2:");
+        }
+    }
+    println!("{}", image.render(&composed));
+
+    match options.export {
+        Some((path, Format::Pbm)) => fs::write(path, image.pbm(&composed))?,
+        Some((path, Format::Ppm)) => fs::write(path, image.ppm(&composed))?,
+        None => {}
+    }
+
+    Ok(())
 }
@@ -1,3 +1,130 @@
-fn main() {
-    println!("Hello, world!");
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+
+// x grows to the right and y downwards, as in the map
+type Point = (i64, i64);
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+// direction from one point to another reduced to lowest terms, and how many steps of it apart
+// they are
+fn direction(from: Point, to: Point) -> (Point, i64) {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let steps = gcd(dx, dy);
+    ((dx / steps, dy / steps), steps)
+}
+
+// clockwise order starting from straight up, compared exactly: first by which half of the turn
+// the direction falls in, then by the sign of the cross product
+fn compare_angle(a: Point, b: Point) -> Ordering {
+    let half = |(dx, dy): Point| (dx < 0 || (dx == 0 && dy > 0)) as u8;
+    half(a)
+        .cmp(&half(b))
+        .then_with(|| (b.0 * a.1).cmp(&(a.0 * b.1)))
+}
+
+fn parse(input: &str) -> Vec<Point> {
+    input
+        .lines()
+        .enumerate()
+        .flat_map(|(y, line)| {
+            line.chars()
+                .enumerate()
+                .filter(|(_, c)| *c == '#')
+                .map(move |(x, _)| (x as i64, y as i64))
+        })
+        .collect()
+}
+
+// asteroid that can see the most others, with how many it sees
+fn best_station(asteroids: &[Point]) -> Option<(Point, usize)> {
+    asteroids
+        .iter()
+        .map(|station| {
+            let visible: HashSet<Point> = asteroids
+                .iter()
+                .filter(|a| *a != station)
+                .map(|a| direction(*station, *a).0)
+                .collect();
+            (*station, visible.len())
+        })
+        .max_by_key(|(_, visible)| *visible)
+}
+
+// asteroids in the order the laser at the station destroys them
+struct Vaporization {
+    // asteroids in each direction nearest first, directions in the order the laser passes them
+    lines: Vec<VecDeque<Point>>,
+    next_line: usize,
+    remaining: usize,
+}
+
+impl Vaporization {
+    fn new(station: Point, asteroids: &[Point]) -> Vaporization {
+        let mut lines: HashMap<Point, Vec<(i64, Point)>> = HashMap::new();
+        for asteroid in asteroids.iter().filter(|a| **a != station) {
+            let (direction, steps) = direction(station, *asteroid);
+            lines.entry(direction).or_default().push((steps, *asteroid));
+        }
+        let mut lines: Vec<(Point, Vec<(i64, Point)>)> = lines.into_iter().collect();
+        lines.sort_by(|a, b| compare_angle(a.0, b.0));
+        Vaporization {
+            remaining: lines.iter().map(|(_, line)| line.len()).sum(),
+            lines: lines
+                .into_iter()
+                .map(|(_, mut line)| {
+                    line.sort();
+                    line.into_iter().map(|(_, asteroid)| asteroid).collect()
+                })
+                .collect(),
+            next_line: 0,
+        }
+    }
+}
+
+impl Iterator for Vaporization {
+    type Item = Point;
+
+    fn next(&mut self) -> Option<Point> {
+        if self.remaining == 0 {
+            return None;
+        }
+        loop {
+            let line = self.next_line;
+            self.next_line = (self.next_line + 1) % self.lines.len();
+            if let Some(asteroid) = self.lines[line].pop_front() {
+                self.remaining -= 1;
+                return Some(asteroid);
+            }
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    let asteroids = parse(&fs::read_to_string("input.txt")?);
+    let (station, visible) = best_station(&asteroids)
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "no asteroids in the map"))?;
+
+    println!("Part 1: {:?}", visible);
+
+    let (x, y) = Vaporization::new(station, &asteroids)
+        .nth(199)
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                "fewer than 200 asteroids to vaporize",
+            )
+        })?;
+
+    println!("Part 2: {:?}", x * 100 + y);
+
+    Ok(())
 }
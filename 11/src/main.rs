@@ -1,3 +1,105 @@
-fn main() {
-    println!("Hello, world!");
+use intcode::device::{DeviceBus, HullRobot};
+use intcode::IntCodeMachine;
+use std::cell::RefCell;
+use std::env;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::rc::Rc;
+
+const WHITE: i64 = 1;
+
+// pixels are scaled up by this much in the exported image
+const SCALE: usize = 8;
+
+fn paint(instructions: &[i64], starting_colour: i64) -> Result<Rc<RefCell<HullRobot>>> {
+    let mut intcode_machine = IntCodeMachine::new(instructions.to_vec());
+    let robot = Rc::new(RefCell::new(HullRobot::new(starting_colour)));
+    let mut bus = DeviceBus::new();
+    bus.attach(robot.clone());
+    bus.run(&mut intcode_machine)?;
+    Ok(robot)
+}
+
+// white panels within the smallest rectangle holding them all
+fn hull_grid(robot: &HullRobot) -> Vec<Vec<bool>> {
+    let white: Vec<(i64, i64)> = robot
+        .panels
+        .iter()
+        .filter(|(_, colour)| **colour == WHITE)
+        .map(|(position, _)| *position)
+        .collect();
+    let min_x = white.iter().map(|p| p.0).min().unwrap_or(0);
+    let max_x = white.iter().map(|p| p.0).max().unwrap_or(-1);
+    let min_y = white.iter().map(|p| p.1).min().unwrap_or(0);
+    let max_y = white.iter().map(|p| p.1).max().unwrap_or(-1);
+    (min_y..=max_y)
+        .map(|y| {
+            (min_x..=max_x)
+                .map(|x| robot.panels.get(&(x, y)) == Some(&WHITE))
+                .collect()
+        })
+        .collect()
+}
+
+fn render(grid: &[Vec<bool>]) -> String {
+    let mut rendered = String::new();
+    for row in grid {
+        rendered.extend(row.iter().map(|white| if *white { '#' } else { ' ' }));
+        rendered.push('\n');
+    }
+    rendered
+}
+
+// plain bitmap with white panels as white on black
+fn pbm(grid: &[Vec<bool>]) -> String {
+    let width = grid.first().map_or(0, |row| row.len());
+    let mut image = format!("P1\n{} {}\n", width * SCALE, grid.len() * SCALE);
+    for row in grid {
+        let line: Vec<&str> = row
+            .iter()
+            .flat_map(|white| [if *white { "0" } else { "1" }; SCALE])
+            .collect();
+        for _ in 0..SCALE {
+            image += &line.join(" ");
+            image.push('\n');
+        }
+    }
+    image
+}
+
+// where to write the hull as an image, if anywhere
+fn export_path() -> Result<Option<String>> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.as_slice() {
+        [] => Ok(None),
+        [flag, path] if flag == "--export" => Ok(Some(path.clone())),
+        _ => Err(Error::new(
+            ErrorKind::InvalidInput,
+            "usage: aoc19_11 [--export HULL.pbm]",
+        )),
+    }
+}
+
+fn main() -> Result<()> {
+    let export = export_path()?;
+    let instructions = intcode::parse("input.txt")?;
+
+    let robot = paint(&instructions, 0)?;
+    println!("Part 1: {:?}", robot.borrow().painted.len());
+
+    let robot = paint(&instructions, WHITE)?;
+    let grid = hull_grid(&robot.borrow());
+    match ocr::recognise(&grid) {
+        Ok(letters) => println!("Part 2: {}", letters),
+        Err(error) => {
+            eprintln!("{}", error);
+            println!("Part 2:");
+        }
+    }
+    println!("{}", render(&grid));
+    if let Some(path) = export {
+        fs::write(path, pbm(&grid))?;
+    }
+
+    Ok(())
 }
@@ -1,3 +1,144 @@
-fn main() {
-    println!("Hello, world!");
+use std::env;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+
+fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+// bodies are stored axis by axis, since every axis evolves independently of the others
+#[derive(Clone, PartialEq, Eq)]
+struct Axis {
+    positions: Vec<i64>,
+    velocities: Vec<i64>,
+}
+
+impl Axis {
+    fn step(&mut self) {
+        for i in 0..self.positions.len() {
+            for j in 0..self.positions.len() {
+                self.velocities[i] += (self.positions[j] - self.positions[i]).signum();
+            }
+        }
+        for (position, velocity) in self.positions.iter_mut().zip(self.velocities.iter()) {
+            *position += velocity;
+        }
+    }
+
+    // steps until the axis first returns to its starting state; each step can be undone, so the
+    // starting state is always the first one to repeat
+    fn period(&self) -> u64 {
+        let mut axis = self.clone();
+        let mut steps = 0;
+        loop {
+            axis.step();
+            steps += 1;
+            if axis == *self {
+                return steps;
+            }
+        }
+    }
+}
+
+// lines like <x=-1, y=0, z=2>, every body needs the same axes
+fn parse(input: &str) -> Result<Vec<Axis>> {
+    let mut axes: Vec<Axis> = vec![];
+    for (i, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let coordinates: Vec<i64> = line
+            .trim_start_matches('<')
+            .trim_end_matches('>')
+            .split(',')
+            .map(|component| {
+                component
+                    .split_once('=')
+                    .and_then(|(_, value)| value.trim().parse().ok())
+                    .ok_or_else(|| {
+                        invalid(format!(
+                            "line {}: invalid coordinate {:?}",
+                            i + 1,
+                            component
+                        ))
+                    })
+            })
+            .collect::<Result<_>>()?;
+        if axes.is_empty() {
+            axes = vec![
+                Axis {
+                    positions: vec![],
+                    velocities: vec![],
+                };
+                coordinates.len()
+            ];
+        } else if coordinates.len() != axes.len() {
+            return Err(invalid(format!(
+                "line {}: expected {} coordinates, found {}",
+                i + 1,
+                axes.len(),
+                coordinates.len()
+            )));
+        }
+        for (axis, coordinate) in axes.iter_mut().zip(coordinates) {
+            axis.positions.push(coordinate);
+            axis.velocities.push(0);
+        }
+    }
+    Ok(axes)
+}
+
+fn total_energy(axes: &[Axis]) -> i64 {
+    let bodies = axes.first().map_or(0, |axis| axis.positions.len());
+    (0..bodies)
+        .map(|body| {
+            let potential: i64 = axes.iter().map(|axis| axis.positions[body].abs()).sum();
+            let kinetic: i64 = axes.iter().map(|axis| axis.velocities[body].abs()).sum();
+            potential * kinetic
+        })
+        .sum()
+}
+
+fn gcd(a: u128, b: u128) -> u128 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+// None if the result doesn't fit
+fn lcm(a: u128, b: u128) -> Option<u128> {
+    (a / gcd(a, b)).checked_mul(b)
+}
+
+// steps until the whole system repeats, the lowest common multiple of the axis periods
+fn period(axes: &[Axis]) -> Result<u128> {
+    axes.iter().try_fold(1, |period, axis| {
+        lcm(period, axis.period() as u128)
+            .ok_or_else(|| invalid("period is too large to represent".to_string()))
+    })
+}
+
+fn main() -> Result<()> {
+    let steps: usize = match env::args().nth(1) {
+        Some(steps) => steps
+            .parse()
+            .map_err(|_| invalid(format!("invalid step count {}", steps)))?,
+        None => 1000,
+    };
+    let axes = parse(&fs::read_to_string("input.txt")?)?;
+
+    let mut simulated = axes.clone();
+    for _ in 0..steps {
+        for axis in simulated.iter_mut() {
+            axis.step();
+        }
+    }
+    println!("Part 1: {:?}", total_energy(&simulated));
+
+    println!("Part 2: {:?}", period(&axes)?);
+
+    Ok(())
 }
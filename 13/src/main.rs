@@ -1,172 +1,164 @@
-use std::collections::VecDeque;
-use std::fs;
-use std::io::Result;
-
-struct IntCodeMachine {
-    instructions: Vec<i64>,
-    position: usize,
-    relative_base: i64,
-    len_instructions: usize,
+mod terminal;
+
+use intcode::device::{DeviceBus, Joystick, Screen, BLOCK};
+use intcode::patch::PatchSpec;
+use intcode::IntCodeMachine;
+use std::cell::RefCell;
+use std::env;
+use std::io::{self, Error, ErrorKind, IsTerminal, Result, Write};
+use std::process;
+use std::rc::Rc;
+use std::thread;
+use std::time::Duration;
+use terminal::{Key, RawMode};
+
+const USAGE: &str = "usage: aoc19_13 [--watch] [--play] [--delay MILLISECONDS]";
+
+// with neither watch nor play the answers are printed without drawing anything
+struct Options {
+    watch: bool,
+    play: bool,
+    delay: Duration,
 }
 
-impl IntCodeMachine {
-    fn new(mut instructions: Vec<i64>) -> IntCodeMachine {
-        let len_instructions = instructions.len();
-        IntCodeMachine {
-            instructions: instructions,
-            position: 0,
-            relative_base: 0,
-            len_instructions: len_instructions,
+fn parse_args() -> Result<Options> {
+    let mut options = Options {
+        watch: false,
+        play: false,
+        delay: Duration::from_millis(50),
+    };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--watch" => options.watch = true,
+            "--play" => options.play = true,
+            "--delay" => {
+                let delay = args
+                    .next()
+                    .and_then(|delay| delay.parse().ok())
+                    .ok_or_else(|| Error::new(ErrorKind::InvalidInput, USAGE))?;
+                options.delay = Duration::from_millis(delay);
+            }
+            _ => return Err(Error::new(ErrorKind::InvalidInput, USAGE)),
         }
     }
+    Ok(options)
+}
 
-    fn proceed_until_halt(&mut self, mut input: VecDeque<i64>) -> (VecDeque<i64>, i64) {
-        let mut output: VecDeque<i64> = VecDeque::new();
-        while self.position < self.len_instructions && self.instructions[self.position] != 99 {
-            let opcode_param: i64 = self.instructions[self.position];
-
-            let opcode_size: u32 = 2;
-            let opcode: i64 = opcode_param % 10_i64.pow(opcode_size);
-
-            let interval: usize;
-
-            match opcode {
-                1 => {
-                    let param_values = self.get_param_values(3);
-                    let target: usize = param_values[2].0;
-                    self.instructions[target] = param_values[0].1 + param_values[1].1;
-                    interval = 4;
-                }
-                2 => {
-                    let param_values = self.get_param_values(3);
-                    let target: usize = param_values[2].0;
-                    self.instructions[target] = param_values[0].1 * param_values[1].1;
-                    interval = 4;
-                }
-                3 => {
-                    if input.is_empty() {
-                        return (output, 2);
-                    }
-                    let param_values = self.get_param_values(1);
-                    let target: usize = param_values[0].0;
-                    self.instructions[target] = input.pop_front().expect("input deque empty");
-                    interval = 2;
-                }
-                4 => {
-                    let param_values = self.get_param_values(1);
-                    output.push_back(param_values[0].1);
-                    interval = 2;
-                }
-                5 => {
-                    let param_values = self.get_param_values(2);
-                    if param_values[0].1 != 0 {
-                        self.position = param_values[1].1.try_into().unwrap();
-                        interval = 0;
-                    } else {
-                        interval = 3;
-                    }
-                }
-                6 => {
-                    let param_values = self.get_param_values(2);
-                    if param_values[0].1 == 0 {
-                        self.position = param_values[1].1.try_into().unwrap();
-                        interval = 0;
-                    } else {
-                        interval = 3;
-                    }
-                }
-                7 => {
-                    let param_values = self.get_param_values(3);
-                    let target: usize = param_values[2].0;
-                    self.instructions[target] = if param_values[0].1 < param_values[1].1 {
-                        1
-                    } else {
-                        0
-                    };
-                    interval = 4;
-                }
-                8 => {
-                    let param_values = self.get_param_values(3);
-                    let target: usize = param_values[2].0;
-                    self.instructions[target] = if param_values[0].1 == param_values[1].1 {
-                        1
-                    } else {
-                        0
-                    };
-                    interval = 4;
-                }
-                9 => {
-                    let param_values = self.get_param_values(1);
-                    let relative_base_change = param_values[0].1;
-                    self.relative_base += relative_base_change;
-                    interval = 2;
-                }
-
-                _ => return (output, 1),
-            }
-
-            self.position += interval;
-        }
-        return (output, 0);
+// keeps the paddle under the ball
+fn autopilot(screen: &Screen) -> i64 {
+    match (screen.ball, screen.paddle) {
+        (Some(ball), Some(paddle)) => (ball.0 - paddle.0).signum(),
+        _ => 0,
     }
+}
 
-    fn extend_instructions(&mut self, length: usize) {
-        for _ in self.instructions.len()..=length {
-            self.instructions.push(0);
-        }
+// plays the game in free play mode until the program halts, the controller picks the
+// joystick position every time the game asks for it
+fn play<F: FnMut(&Screen) -> i64 + 'static>(
+    instructions: &[i64],
+    mut controller: F,
+) -> Result<Screen> {
+    let mut intcode_machine = IntCodeMachine::new(instructions.to_vec());
+    intcode_machine.apply_patch(PatchSpec::new().set(0, 2))?;
+
+    let screen = Rc::new(RefCell::new(Screen::new()));
+    let controlled = screen.clone();
+    let mut bus = DeviceBus::new();
+    bus.attach(screen.clone());
+    bus.attach(Joystick::new(move || controller(&controlled.borrow())));
+    bus.run(&mut intcode_machine)?;
+    // drops the controller, and with it any terminal state it holds
+    drop(bus);
+
+    Ok(screen.take())
+}
+
+// final score once every block is gone
+fn score(screen: &Screen) -> Result<i64> {
+    let blocks = screen.count(BLOCK);
+    if blocks > 0 {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("game over with {} blocks left", blocks),
+        ));
     }
+    screen
+        .score
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "game never reported a score"))
+}
 
-    fn get_param_values(&mut self, num_parameters: usize) -> Vec<(usize, i64)> {
-        let mut param_values: Vec<(usize, i64)> = vec![];
-        for i in 1..=num_parameters {
-            let parameter_mode =
-                self.instructions[self.position] / 10_i64.pow((i + 1).try_into().unwrap()) % 10;
-            if parameter_mode == 0 {
-                // position mode
-                let position: usize = self.instructions[self.position + i].try_into().unwrap();
-                self.extend_instructions(position);
-                param_values.push((position, self.instructions[position]))
-            } else if parameter_mode == 1 {
-                // immediate mode
-                param_values.push((usize::MAX, self.instructions[self.position + i]))
-            } else {
-                // relative mode
-                let offset = self.instructions[self.position + i];
-                let position: usize = (self.relative_base + offset).try_into().unwrap();
-                self.extend_instructions(position);
-                param_values.push((position, self.instructions[position]))
+// draws every frame, taking the joystick from the keyboard when playing in a terminal and
+// from the autopilot otherwise
+fn run_in_terminal(instructions: &[i64], options: &Options) -> Result<()> {
+    let keyboard = options.play && io::stdin().is_terminal();
+    if options.play && !keyboard {
+        eprintln!("no terminal attached, falling back to the autopilot");
+    }
+    let mut raw_mode = if keyboard {
+        Some(RawMode::enable()?)
+    } else {
+        print!("{}", terminal::CLEAR);
+        None
+    };
+    let delay = options.delay;
+
+    let screen = play(instructions, move |screen| {
+        print!("{}", terminal::render(screen));
+        let _ = io::stdout().flush();
+        thread::sleep(delay);
+        let key = match raw_mode.as_ref() {
+            Some(raw_mode) => raw_mode.read_key().unwrap_or(None),
+            None => return autopilot(screen),
+        };
+        match key {
+            Some(Key::Left) => -1,
+            Some(Key::Right) => 1,
+            Some(Key::Quit) => {
+                raw_mode.take();
+                process::exit(0);
             }
+            Some(Key::Neutral) | None => 0,
         }
-        return param_values;
+    })?;
+
+    print!("{}", terminal::render(&screen));
+    match screen.count(BLOCK) {
+        0 => println!(
+            "Cleared every block, final score {}",
+            screen.score.unwrap_or(0)
+        ),
+        blocks => println!(
+            "Game over with {} blocks left, score {}",
+            blocks,
+            screen.score.unwrap_or(0)
+        ),
     }
-}
-
-fn parse() -> Result<Vec<i64>> {
-    return Ok(fs::read_to_string("input.txt")?
-        .split(",")
-        .map(|v| v.parse().unwrap())
-        .collect());
+    Ok(())
 }
 
 fn main() -> Result<()> {
-    let instructions = parse()?;
+    let options = parse_args()?;
+    let instructions = intcode::parse("input.txt")?;
+
+    if options.watch || options.play {
+        return run_in_terminal(&instructions, &options);
+    }
 
     let mut intcode_machine = IntCodeMachine::new(instructions.clone());
 
-    let (tiles, _) = intcode_machine.proceed_until_halt(VecDeque::from([]));
+    let screen = Rc::new(RefCell::new(Screen::new()));
+    let mut bus = DeviceBus::new();
+    bus.attach(screen.clone());
+    bus.run(&mut intcode_machine)?;
 
-    let mut part_1_answer = 0;
+    let part_1_answer = screen.borrow().count(BLOCK);
 
-    let mut i = 0;
+    println!("Part 1: {:?}", part_1_answer);
 
-    while i < tiles.len() {
-        if tiles[i + 2] == 2 {
-            part_1_answer += 1
-        }
-        i += 3
-    }
+    let screen = play(&instructions, autopilot)?;
 
-    println!("Part 1: {:?}", part_1_answer);
+    println!("Part 2: {:?}", score(&screen)?);
 
     Ok(())
 }
@@ -0,0 +1,106 @@
+// Drawing the arcade with ANSI escape codes and reading the keyboard without waiting for enter.
+// Raw mode is set up with stty, so it needs a unix terminal on stdin.
+
+use intcode::device::{Screen, BALL, BLOCK, PADDLE, WALL};
+use std::io::{self, Error, ErrorKind, Read, Result, Write};
+use std::process::{Command, Stdio};
+
+pub const CLEAR: &str = "\x1b[2J";
+const HOME: &str = "\x1b[H";
+const HIDE_CURSOR: &str = "\x1b[?25l";
+const SHOW_CURSOR: &str = "\x1b[?25h";
+
+pub enum Key {
+    Left,
+    Right,
+    Neutral,
+    Quit,
+}
+
+fn stty(args: &[&str]) -> Result<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .output()?;
+    if !output.status.success() {
+        return Err(Error::new(
+            ErrorKind::Unsupported,
+            format!(
+                "stty failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+// puts the terminal in raw mode with reads that never block, restoring it when dropped
+pub struct RawMode {
+    saved: String,
+}
+
+impl RawMode {
+    pub fn enable() -> Result<RawMode> {
+        let saved = stty(&["-g"])?;
+        // with -isig ctrl-c arrives as a key, so quitting always goes through the restore in drop
+        stty(&["-icanon", "-echo", "-isig", "min", "0", "time", "0"])?;
+        print!("{}{}", CLEAR, HIDE_CURSOR);
+        io::stdout().flush()?;
+        Ok(RawMode { saved })
+    }
+
+    // the last key pressed since the previous call, a/d or the arrow keys to move, q or ctrl-c
+    // to quit
+    pub fn read_key(&self) -> Result<Option<Key>> {
+        let mut buffer = [0; 64];
+        let read = io::stdin().read(&mut buffer)?;
+        let mut key = None;
+        let mut bytes = buffer[..read].iter();
+        while let Some(byte) = bytes.next() {
+            key = match byte {
+                b'a' | b'A' => Some(Key::Left),
+                b'd' | b'D' => Some(Key::Right),
+                b's' | b'S' | b' ' => Some(Key::Neutral),
+                b'q' | b'Q' | 0x03 => return Ok(Some(Key::Quit)),
+                // arrow keys are ESC [ C and ESC [ D
+                0x1b => match (bytes.next(), bytes.next()) {
+                    (Some(b'['), Some(b'D')) => Some(Key::Left),
+                    (Some(b'['), Some(b'C')) => Some(Key::Right),
+                    _ => key,
+                },
+                _ => key,
+            };
+        }
+        Ok(key)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = stty(&[&self.saved]);
+        print!("{}", SHOW_CURSOR);
+        let _ = io::stdout().flush();
+    }
+}
+
+// tiles in colour, resetting after each so the rest of the terminal is left alone
+fn coloured(tile: i64) -> &'static str {
+    match tile {
+        WALL => "\x1b[37m#\x1b[0m",
+        BLOCK => "\x1b[36m=\x1b[0m",
+        PADDLE => "\x1b[33m-\x1b[0m",
+        BALL => "\x1b[31mo\x1b[0m",
+        _ => " ",
+    }
+}
+
+// the screen as a frame that redraws over the previous one
+pub fn render(screen: &Screen) -> String {
+    format!(
+        "{}Score: {}  Blocks: {}\r\n{}",
+        HOME,
+        screen.score.unwrap_or(0),
+        screen.count(BLOCK),
+        screen.render(coloured, "\r\n")
+    )
+}
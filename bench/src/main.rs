@@ -0,0 +1,297 @@
+// Times every day's solution and a set of intcode micro-benchmarks:
+//
+//   cargo run --release -- [--iterations N] [--warmup N] [--only NAME] [--save FILE]
+//                          [--baseline FILE] [--threshold PERCENT]
+//
+// Days are built in release mode and run from their own directory, skipping any without an
+// input.txt. Part timings come from when the "Part 1" and "Part 2" lines are printed, so part 1
+// includes starting the process and parsing the input. A baseline file holds one
+// `name median_nanoseconds` line per benchmark, as written by --save; comparing against one
+// flags every benchmark whose median got slower by more than the threshold.
+
+use intcode::{IntCodeMachine, HALTED};
+use std::collections::{BTreeMap, VecDeque};
+use std::env;
+use std::fs;
+use std::hint::black_box;
+use std::io::{BufRead, BufReader, Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+use std::time::{Duration, Instant};
+
+// name, source compiled with intcode::compiler and the input it runs on
+const MICRO_BENCHMARKS: [(&str, &str, &[i64]); 3] = [
+    (
+        "intcode/loop",
+        "fn main() {
+            var n = input();
+            var i = 0;
+            var sum = 0;
+            while (i < n) {
+                sum = sum + i * i;
+                i = i + 1;
+            }
+            output(sum);
+        }",
+        &[200_000],
+    ),
+    (
+        "intcode/recursion",
+        "fn fib(n) {
+            if (n < 2) {
+                return n;
+            }
+            return fib(n - 1) + fib(n - 2);
+        }
+
+        fn main() {
+            output(fib(input()));
+        }",
+        &[22],
+    ),
+    (
+        // every level of recursion grows memory by a stack frame
+        "intcode/memory",
+        "fn depth(n) {
+            if (n == 0) {
+                return 0;
+            }
+            return depth(n - 1) + 1;
+        }
+
+        fn main() {
+            output(depth(input()));
+        }",
+        &[200_000],
+    ),
+];
+
+struct Options {
+    iterations: usize,
+    warmup: usize,
+    only: Option<String>,
+    save: Option<PathBuf>,
+    baseline: Option<PathBuf>,
+    // allowed slowdown in percent before a benchmark counts as a regression
+    threshold: f64,
+}
+
+// one run of a benchmark, giving a duration for every timing it measures
+type Run = Box<dyn FnMut() -> Result<Vec<(String, Duration)>>>;
+
+struct Stats {
+    median: Duration,
+    min: Duration,
+    max: Duration,
+}
+
+impl Stats {
+    fn new(samples: &mut [Duration]) -> Stats {
+        samples.sort();
+        Stats {
+            median: samples[samples.len() / 2],
+            min: samples[0],
+            max: samples[samples.len() - 1],
+        }
+    }
+}
+
+fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidInput, message)
+}
+
+fn parse_args() -> Result<Options> {
+    let mut options = Options {
+        iterations: 10,
+        warmup: 2,
+        only: None,
+        save: None,
+        baseline: None,
+        threshold: 10.0,
+    };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| invalid(format!("{} needs a value", arg)))?;
+        let number = |value: &str| {
+            value
+                .parse::<f64>()
+                .map_err(|_| invalid(format!("invalid value {} for {}", value, arg)))
+        };
+        match arg.as_str() {
+            "--iterations" => options.iterations = number(&value)?.max(1.0) as usize,
+            "--warmup" => options.warmup = number(&value)? as usize,
+            "--only" => options.only = Some(value),
+            "--save" => options.save = Some(PathBuf::from(value)),
+            "--baseline" => options.baseline = Some(PathBuf::from(value)),
+            "--threshold" => options.threshold = number(&value)?,
+            _ => return Err(invalid(format!("unknown flag {}", arg))),
+        }
+    }
+    Ok(options)
+}
+
+fn micro_benchmark(source: &str, input: &'static [i64]) -> Result<Run> {
+    let program = intcode::compiler::compile(source)?;
+    Ok(Box::new(move || {
+        let start = Instant::now();
+        let mut machine = IntCodeMachine::new(program.clone());
+        let (output, code) = machine.proceed_until_halt(VecDeque::from(input.to_vec()));
+        let elapsed = start.elapsed();
+        if code != HALTED {
+            return Err(invalid(format!("benchmark stopped with code {}", code)));
+        }
+        black_box(output);
+        Ok(vec![(String::new(), elapsed)])
+    }))
+}
+
+fn package_name(day: &Path) -> Result<String> {
+    fs::read_to_string(day.join("Cargo.toml"))?
+        .lines()
+        .find_map(|line| line.strip_prefix("name = "))
+        .map(|name| name.trim_matches('"').to_string())
+        .ok_or_else(|| invalid(format!("no package name in {}", day.display())))
+}
+
+fn day_benchmark(day: PathBuf) -> Result<Run> {
+    let status = Command::new("cargo")
+        .args(["build", "--release", "--quiet"])
+        .current_dir(&day)
+        .status()?;
+    if !status.success() {
+        return Err(invalid(format!("failed to build {}", day.display())));
+    }
+    let binary = day.join("target/release").join(package_name(&day)?);
+    Ok(Box::new(move || {
+        let start = Instant::now();
+        let mut child = Command::new(&binary)
+            .current_dir(&day)
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let mut timings = vec![];
+        let mut part_1 = None;
+        for line in BufReader::new(child.stdout.take().unwrap()).lines() {
+            let line = line?;
+            if line.starts_with("Part 1") {
+                part_1 = Some(start.elapsed());
+                timings.push(("/part1".to_string(), start.elapsed()));
+            } else if line.starts_with("Part 2") {
+                let elapsed = start.elapsed();
+                timings.push(("/part2".to_string(), elapsed - part_1.unwrap_or_default()));
+            }
+        }
+        if !child.wait()?.success() {
+            return Err(invalid(format!("{} failed", binary.display())));
+        }
+        Ok(timings)
+    }))
+}
+
+fn benchmarks(root: &Path, only: Option<&str>) -> Result<Vec<(String, Run)>> {
+    let skip = |name: &str| only.is_some_and(|only| !name.contains(only));
+    let mut benchmarks = vec![];
+    for (name, source, input) in MICRO_BENCHMARKS {
+        if skip(name) {
+            continue;
+        }
+        benchmarks.push((name.to_string(), micro_benchmark(source, input)?));
+    }
+    for day in 1..=25 {
+        let name = format!("day{:02}", day);
+        let dir = root.join(format!("{:02}", day));
+        if skip(&name) {
+            continue;
+        }
+        if !dir.join("input.txt").exists() {
+            println!("{}: skipped, no input.txt", name);
+            continue;
+        }
+        benchmarks.push((name, day_benchmark(dir)?));
+    }
+    Ok(benchmarks)
+}
+
+fn load_baseline(path: &Path) -> Result<BTreeMap<String, Duration>> {
+    let mut baseline = BTreeMap::new();
+    for (i, line) in fs::read_to_string(path)?.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let nanos = line
+            .split_once(' ')
+            .and_then(|(name, nanos)| Some((name, nanos.trim().parse().ok()?)));
+        match nanos {
+            Some((name, nanos)) => baseline.insert(name.to_string(), Duration::from_nanos(nanos)),
+            None => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("line {}: expected name and nanoseconds", i + 1),
+                ))
+            }
+        };
+    }
+    Ok(baseline)
+}
+
+fn main() -> Result<()> {
+    let options = parse_args()?;
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+    let baseline = match &options.baseline {
+        Some(path) => load_baseline(path)?,
+        None => BTreeMap::new(),
+    };
+
+    let mut results: BTreeMap<String, Stats> = BTreeMap::new();
+    for (name, mut run) in benchmarks(&root, options.only.as_deref())? {
+        for _ in 0..options.warmup {
+            run()?;
+        }
+        let mut samples: BTreeMap<String, Vec<Duration>> = BTreeMap::new();
+        for _ in 0..options.iterations {
+            for (timing, elapsed) in run()? {
+                samples
+                    .entry(name.clone() + &timing)
+                    .or_default()
+                    .push(elapsed);
+            }
+        }
+        if samples.is_empty() {
+            println!("{}: no parts printed", name);
+        }
+        for (timing, mut samples) in samples {
+            results.insert(timing, Stats::new(&mut samples));
+        }
+    }
+
+    let mut regressions = 0;
+    for (name, stats) in results.iter() {
+        let mut line = format!(
+            "{:<20} median {:>12.3?}  min {:>12.3?}  max {:>12.3?}",
+            name, stats.median, stats.min, stats.max
+        );
+        if let Some(base) = baseline.get(name) {
+            let change = 100.0 * (stats.median.as_secs_f64() / base.as_secs_f64() - 1.0);
+            line += &format!("  {:+.1}% vs {:.3?}", change, base);
+            if change > options.threshold {
+                line += "  REGRESSION";
+                regressions += 1;
+            }
+        }
+        println!("{}", line);
+    }
+
+    if let Some(path) = &options.save {
+        let lines: Vec<String> = results
+            .iter()
+            .map(|(name, stats)| format!("{} {}\n", name, stats.median.as_nanos()))
+            .collect();
+        fs::write(path, lines.concat())?;
+    }
+    if regressions > 0 {
+        println!("{} regressions", regressions);
+        process::exit(1);
+    }
+    Ok(())
+}
@@ -0,0 +1,261 @@
+// Running machines as futures, for when a blocked thread per machine isn't an option. Opcode 3
+// waits on an AsyncInput and opcode 4 on an AsyncOutput, and a long computation yields to other
+// tasks every YIELD_INTERVAL instructions. Machines are connected with channels and run by the
+// single-threaded Executor, or driven to completion with block_on.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::future::{poll_fn, Future};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+
+use crate::{IntCodeMachine, AWAITING_INPUT};
+
+const YIELD_INTERVAL: u64 = 10_000;
+
+pub trait AsyncInput {
+    // Ready(None) once no more input will ever arrive
+    fn poll_read(&mut self, cx: &mut Context) -> Poll<Option<i64>>;
+}
+
+pub trait AsyncOutput {
+    fn poll_write(&mut self, cx: &mut Context, value: i64) -> Poll<()>;
+}
+
+// inputs that are all known up front
+impl AsyncInput for VecDeque<i64> {
+    fn poll_read(&mut self, _cx: &mut Context) -> Poll<Option<i64>> {
+        Poll::Ready(self.pop_front())
+    }
+}
+
+impl AsyncOutput for VecDeque<i64> {
+    fn poll_write(&mut self, _cx: &mut Context, value: i64) -> Poll<()> {
+        self.push_back(value);
+        Poll::Ready(())
+    }
+}
+
+impl AsyncOutput for Vec<i64> {
+    fn poll_write(&mut self, _cx: &mut Context, value: i64) -> Poll<()> {
+        self.push(value);
+        Poll::Ready(())
+    }
+}
+
+// returns Pending once, so other tasks get a turn
+fn yield_now() -> impl Future<Output = ()> {
+    let mut yielded = false;
+    poll_fn(move |cx| {
+        if yielded {
+            return Poll::Ready(());
+        }
+        yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    })
+}
+
+impl IntCodeMachine {
+    // runs until the program halts or stops, returning the same codes as proceed_until_halt;
+    // AWAITING_INPUT means the input was closed while the program still wanted more
+    pub async fn run_async<I: AsyncInput, O: AsyncOutput>(
+        &mut self,
+        input: &mut I,
+        output: &mut O,
+    ) -> i64 {
+        let mut pending_input = VecDeque::new();
+        let mut pending_output = VecDeque::new();
+        let mut steps: u64 = 0;
+        loop {
+            let wants_input = self.exit_code.is_none()
+                && self.position < self.len_instructions
+                && self.instructions[self.position] % 100 == 3
+                && self.rewound_input.is_empty();
+            if wants_input && pending_input.is_empty() {
+                match poll_fn(|cx| input.poll_read(cx)).await {
+                    Some(value) => pending_input.push_back(value),
+                    None => return AWAITING_INPUT,
+                }
+            }
+            let code = self.step(&mut pending_input, &mut pending_output);
+            while let Some(value) = pending_output.pop_front() {
+                poll_fn(|cx| output.poll_write(cx, value)).await;
+            }
+            if let Some(code) = code {
+                return code;
+            }
+            steps += 1;
+            if steps.is_multiple_of(YIELD_INTERVAL) {
+                yield_now().await;
+            }
+        }
+    }
+}
+
+struct Shared {
+    queue: VecDeque<i64>,
+    // 0 for unbounded
+    capacity: usize,
+    closed: bool,
+    reader: Option<Waker>,
+    writer: Option<Waker>,
+}
+
+// sending half of a channel, the channel closes once it's dropped
+pub struct Sender(Rc<RefCell<Shared>>);
+
+pub struct Receiver(Rc<RefCell<Shared>>);
+
+// single producer, single consumer queue of values; sends wait while capacity values are queued
+pub fn channel(capacity: usize) -> (Sender, Receiver) {
+    let shared = Rc::new(RefCell::new(Shared {
+        queue: VecDeque::new(),
+        capacity,
+        closed: false,
+        reader: None,
+        writer: None,
+    }));
+    (Sender(shared.clone()), Receiver(shared))
+}
+
+impl Sender {
+    pub async fn send(&mut self, value: i64) {
+        poll_fn(|cx| self.poll_write(cx, value)).await
+    }
+}
+
+impl AsyncOutput for Sender {
+    fn poll_write(&mut self, cx: &mut Context, value: i64) -> Poll<()> {
+        let mut shared = self.0.borrow_mut();
+        if shared.capacity > 0 && shared.queue.len() >= shared.capacity {
+            shared.writer = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+        shared.queue.push_back(value);
+        if let Some(reader) = shared.reader.take() {
+            reader.wake();
+        }
+        Poll::Ready(())
+    }
+}
+
+impl Drop for Sender {
+    fn drop(&mut self) {
+        let mut shared = self.0.borrow_mut();
+        shared.closed = true;
+        if let Some(reader) = shared.reader.take() {
+            reader.wake();
+        }
+    }
+}
+
+impl Receiver {
+    pub async fn recv(&mut self) -> Option<i64> {
+        poll_fn(|cx| self.poll_read(cx)).await
+    }
+}
+
+impl AsyncInput for Receiver {
+    fn poll_read(&mut self, cx: &mut Context) -> Poll<Option<i64>> {
+        let mut shared = self.0.borrow_mut();
+        match shared.queue.pop_front() {
+            Some(value) => {
+                if let Some(writer) = shared.writer.take() {
+                    writer.wake();
+                }
+                Poll::Ready(Some(value))
+            }
+            None if shared.closed => Poll::Ready(None),
+            None => {
+                shared.reader = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+type Task = Pin<Box<dyn Future<Output = ()>>>;
+
+struct TaskWaker {
+    task: usize,
+    ready: Arc<Mutex<VecDeque<usize>>>,
+}
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref()
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.ready.lock().unwrap().push_back(self.task);
+    }
+}
+
+// runs spawned tasks on the current thread, polling each only once it has been woken
+#[derive(Default)]
+pub struct Executor {
+    tasks: Vec<Option<Task>>,
+    ready: Arc<Mutex<VecDeque<usize>>>,
+}
+
+impl Executor {
+    pub fn new() -> Executor {
+        Executor::default()
+    }
+
+    pub fn spawn<F: Future<Output = ()> + 'static>(&mut self, future: F) {
+        self.ready.lock().unwrap().push_back(self.tasks.len());
+        self.tasks.push(Some(Box::pin(future)));
+    }
+
+    // polls tasks until every one has finished or the remaining ones are all waiting on each
+    // other, returning the number of unfinished tasks
+    pub fn run(&mut self) -> usize {
+        loop {
+            let next = self.ready.lock().unwrap().pop_front();
+            let Some(task) = next else {
+                break;
+            };
+            let Some(future) = self.tasks[task].as_mut() else {
+                continue;
+            };
+            let waker = Waker::from(Arc::new(TaskWaker {
+                task,
+                ready: self.ready.clone(),
+            }));
+            if future
+                .as_mut()
+                .poll(&mut Context::from_waker(&waker))
+                .is_ready()
+            {
+                self.tasks[task] = None;
+            }
+        }
+        self.tasks.iter().filter(|t| t.is_some()).count()
+    }
+}
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+// drives a single future to completion on the current thread
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = Box::pin(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+        thread::park();
+    }
+}
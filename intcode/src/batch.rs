@@ -0,0 +1,113 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use crate::patch::PatchSpec;
+use crate::{IntCodeMachine, INVALID_OPCODE};
+
+// code of a job stopped for running more than max_steps instructions
+pub const STEP_LIMIT: i64 = 4;
+
+#[derive(Clone, Debug, Default)]
+pub struct Job {
+    // written to the program before it starts
+    pub patch: PatchSpec,
+    pub input: Vec<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct JobResult {
+    // position of the job in the batch
+    pub index: usize,
+    pub code: i64,
+    pub output: Vec<i64>,
+    pub memory: Vec<i64>,
+    pub error: Option<String>,
+}
+
+fn run_job(program: &[i64], index: usize, job: Job, max_steps: u64) -> JobResult {
+    let mut machine = IntCodeMachine::new(program.to_vec());
+    if let Err(error) = machine.apply_patch(&job.patch) {
+        return JobResult {
+            index,
+            code: INVALID_OPCODE,
+            output: vec![],
+            memory: machine.instructions,
+            error: Some(error.to_string()),
+        };
+    }
+    let mut input = VecDeque::from(job.input);
+    let mut output = VecDeque::new();
+    for _ in 0..max_steps {
+        if let Some(code) = machine.step(&mut input, &mut output) {
+            return JobResult {
+                index,
+                code,
+                output: output.into(),
+                memory: machine.instructions,
+                error: machine.error,
+            };
+        }
+    }
+    JobResult {
+        index,
+        code: STEP_LIMIT,
+        output: output.into(),
+        memory: machine.instructions,
+        error: Some(format!("Job didn't stop within {} steps", max_steps)),
+    }
+}
+
+// runs every job on its own copy of the program across `threads` worker threads (0 for one
+// per core), results come back in job order; once a result satisfies stop_when no later jobs
+// are started and the results end with the first matching job. Jobs still running after
+// max_steps instructions are stopped with STEP_LIMIT
+pub fn run_batch<I, P>(
+    program: &[i64],
+    jobs: I,
+    threads: usize,
+    max_steps: u64,
+    stop_when: P,
+) -> Vec<JobResult>
+where
+    I: IntoIterator<Item = Job>,
+    I::IntoIter: Send,
+    P: Fn(&JobResult) -> bool + Sync,
+{
+    let threads = if threads == 0 {
+        thread::available_parallelism().map_or(1, |n| n.get())
+    } else {
+        threads
+    };
+    let jobs = Mutex::new(jobs.into_iter().enumerate());
+    let first_match = AtomicUsize::new(usize::MAX);
+    let results = Mutex::new(vec![]);
+
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| loop {
+                // jobs are taken in order, so every job before a match has already started
+                let (index, job) = {
+                    let mut jobs = jobs.lock().unwrap();
+                    match jobs.next() {
+                        Some((index, _)) if index > first_match.load(Ordering::SeqCst) => break,
+                        Some(next) => next,
+                        None => break,
+                    }
+                };
+                let result = run_job(program, index, job, max_steps);
+                if stop_when(&result) {
+                    first_match.fetch_min(index, Ordering::SeqCst);
+                }
+                results.lock().unwrap().push(result);
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|r| r.index);
+    let first_match = first_match.into_inner();
+    results.retain(|r| r.index <= first_match);
+    results
+}
@@ -0,0 +1,542 @@
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind, Result};
+
+use super::parser::{BinOp, Expr, Function, Global, Program, Stmt, UnOp};
+
+// a word of output whose value may only be known once everything has been generated
+#[derive(Clone, Copy)]
+enum Word {
+    Value(i64),
+    // address of a global slot plus an offset
+    Global(usize, i64),
+    // address of a label plus an offset
+    Label(usize, i64),
+    // scale times the frame size of a function, plus an offset
+    Frame(usize, i64, i64),
+    // first address after the globals
+    Stack,
+}
+
+#[derive(Clone, Copy)]
+enum Arg {
+    Pos(Word),
+    Imm(Word),
+    Rel(Word),
+}
+
+impl Arg {
+    fn mode(&self) -> i64 {
+        match self {
+            Arg::Pos(_) => 0,
+            Arg::Imm(_) => 1,
+            Arg::Rel(_) => 2,
+        }
+    }
+
+    fn word(&self) -> Word {
+        match self {
+            Arg::Pos(word) | Arg::Imm(word) | Arg::Rel(word) => *word,
+        }
+    }
+}
+
+fn imm(value: i64) -> Arg {
+    Arg::Imm(Word::Value(value))
+}
+
+fn rel(slot: i64) -> Arg {
+    Arg::Rel(Word::Value(slot))
+}
+
+#[derive(Clone, Copy)]
+enum Variable {
+    // slot in the current frame
+    Local(i64),
+    LocalArray(i64),
+    Global(usize),
+    GlobalArray(usize),
+}
+
+// global slots every program has
+const RET: usize = 0;
+// mirror of the relative base, needed to take the address of local arrays
+const FP: usize = 1;
+
+struct FunctionInfo {
+    label: usize,
+    arity: usize,
+}
+
+fn error(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+fn count_locals(statements: &[Stmt]) -> i64 {
+    statements
+        .iter()
+        .map(|statement| match statement {
+            Stmt::Var(_, _) => 1,
+            Stmt::Array(_, size) => *size as i64,
+            Stmt::If(_, then, otherwise) => count_locals(then) + count_locals(otherwise),
+            Stmt::While(_, body) => count_locals(body),
+            _ => 0,
+        })
+        .sum()
+}
+
+#[derive(Default)]
+struct Codegen {
+    words: Vec<Word>,
+    labels: Vec<Option<usize>>,
+    globals: HashMap<String, Variable>,
+    global_values: Vec<i64>,
+    functions: HashMap<String, FunctionInfo>,
+    frame_sizes: Vec<i64>,
+    // state of the function being generated
+    current: usize,
+    function_name: String,
+    scopes: Vec<HashMap<String, Variable>>,
+    next_local: i64,
+    temp_base: i64,
+    temps: i64,
+    max_temps: i64,
+}
+
+impl Codegen {
+    fn emit(&mut self, opcode: i64, args: &[Arg]) {
+        let mut instruction = opcode;
+        for (i, arg) in args.iter().enumerate() {
+            instruction += arg.mode() * 10_i64.pow((i + 2).try_into().unwrap());
+        }
+        self.words.push(Word::Value(instruction));
+        self.words.extend(args.iter().map(|arg| arg.word()));
+    }
+
+    fn new_label(&mut self) -> usize {
+        self.labels.push(None);
+        self.labels.len() - 1
+    }
+
+    fn place(&mut self, label: usize) {
+        self.labels[label] = Some(self.words.len());
+    }
+
+    fn label(&self, label: usize) -> Arg {
+        Arg::Imm(Word::Label(label, 0))
+    }
+
+    fn temp(&mut self) -> Arg {
+        let slot = self.temp_base + self.temps;
+        self.temps += 1;
+        self.max_temps = self.max_temps.max(self.temps);
+        rel(slot)
+    }
+
+    fn copy(&mut self, from: Arg, to: Arg) {
+        self.emit(1, &[from, imm(0), to]);
+    }
+
+    fn jump(&mut self, label: usize) {
+        let target = self.label(label);
+        self.emit(5, &[imm(1), target]);
+    }
+
+    fn lookup(&self, name: &str) -> Result<Variable> {
+        for scope in self.scopes.iter().rev() {
+            if let Some(variable) = scope.get(name) {
+                return Ok(*variable);
+            }
+        }
+        self.globals.get(name).copied().ok_or_else(|| {
+            error(format!(
+                "undefined variable {} in function {}",
+                name, self.function_name
+            ))
+        })
+    }
+
+    fn declare(&mut self, name: &str, variable: Variable) {
+        self.scopes
+            .last_mut()
+            .unwrap()
+            .insert(name.to_string(), variable);
+    }
+
+    fn program(mut self, program: &Program) -> Result<Vec<i64>> {
+        for name in ["ret", "fp"] {
+            self.global_values.push(0);
+            self.globals.insert(
+                format!("${}", name),
+                Variable::Global(self.global_values.len() - 1),
+            );
+        }
+        for global in program.globals.iter() {
+            let (name, variable) = match global {
+                Global::Scalar(name, value) => {
+                    self.global_values.push(*value);
+                    (name, Variable::Global(self.global_values.len() - 1))
+                }
+                Global::Array(name, size) => {
+                    let slot = self.global_values.len();
+                    self.global_values.extend(vec![0; *size]);
+                    (name, Variable::GlobalArray(slot))
+                }
+            };
+            if self.globals.insert(name.clone(), variable).is_some() {
+                return Err(error(format!("duplicate global {}", name)));
+            }
+        }
+        for function in program.functions.iter() {
+            let label = self.new_label();
+            let info = FunctionInfo {
+                label,
+                arity: function.params.len(),
+            };
+            if ["input", "output"].contains(&function.name.as_str())
+                || self.functions.insert(function.name.clone(), info).is_some()
+            {
+                return Err(error(format!("duplicate function {}", function.name)));
+            }
+        }
+        let main = match self.functions.get("main") {
+            Some(main) if main.arity == 0 => main.label,
+            Some(_) => return Err(error("main can't take parameters".to_string())),
+            None => return Err(error("no main function".to_string())),
+        };
+
+        // point the relative base at the stack, then call main with a return address that halts
+        let halt = self.new_label();
+        self.emit(9, &[Arg::Imm(Word::Stack)]);
+        self.copy(Arg::Imm(Word::Stack), Arg::Pos(Word::Global(FP, 0)));
+        let halt_address = self.label(halt);
+        self.copy(halt_address, rel(0));
+        self.jump(main);
+        self.place(halt);
+        self.words.push(Word::Value(99));
+
+        for (id, function) in program.functions.iter().enumerate() {
+            self.function(id, function)?;
+        }
+        Ok(self.link())
+    }
+
+    fn function(&mut self, id: usize, function: &Function) -> Result<()> {
+        let label = self.functions[&function.name].label;
+        self.place(label);
+        self.current = id;
+        self.function_name = function.name.clone();
+        // slot 0 holds the return address, parameters follow
+        let mut params = HashMap::new();
+        for (i, param) in function.params.iter().enumerate() {
+            if params
+                .insert(param.clone(), Variable::Local(i as i64 + 1))
+                .is_some()
+            {
+                return Err(error(format!(
+                    "duplicate parameter {} in function {}",
+                    param, function.name
+                )));
+            }
+        }
+        self.scopes = vec![params];
+        self.next_local = function.params.len() as i64 + 1;
+        self.temp_base = self.next_local + count_locals(&function.body);
+        self.temps = 0;
+        self.max_temps = 0;
+
+        self.block(&function.body)?;
+        self.statement(&Stmt::Return(None))?;
+
+        self.frame_sizes.push(self.temp_base + self.max_temps);
+        Ok(())
+    }
+
+    fn block(&mut self, statements: &[Stmt]) -> Result<()> {
+        self.scopes.push(HashMap::new());
+        for statement in statements {
+            self.statement(statement)?;
+            self.temps = 0;
+        }
+        self.scopes.pop();
+        Ok(())
+    }
+
+    fn statement(&mut self, statement: &Stmt) -> Result<()> {
+        match statement {
+            Stmt::Var(name, value) => {
+                let value = match value {
+                    Some(value) => self.expr(value)?,
+                    None => imm(0),
+                };
+                let slot = self.next_local;
+                self.next_local += 1;
+                self.copy(value, rel(slot));
+                self.declare(name, Variable::Local(slot));
+            }
+            Stmt::Array(name, size) => {
+                let slot = self.next_local;
+                self.next_local += *size as i64;
+                self.declare(name, Variable::LocalArray(slot));
+            }
+            Stmt::Assign(name, value) => {
+                let value = self.expr(value)?;
+                let target = match self.lookup(name)? {
+                    Variable::Local(slot) => rel(slot),
+                    Variable::Global(slot) => Arg::Pos(Word::Global(slot, 0)),
+                    _ => return Err(error(format!("can't assign to array {}", name))),
+                };
+                self.copy(value, target);
+            }
+            Stmt::AssignIndex(base, index, value) => {
+                if let Some(target) = self.constant_element(base, index)? {
+                    let value = self.expr(value)?;
+                    self.copy(value, target);
+                    return Ok(());
+                }
+                let address = self.address(base, index)?;
+                let value = self.expr(value)?;
+                // patch the target of the copy below with the computed address
+                let store = self.new_label();
+                self.copy(address, Arg::Pos(Word::Label(store, 3)));
+                self.place(store);
+                self.copy(value, Arg::Pos(Word::Value(0)));
+            }
+            Stmt::If(condition, then, otherwise) => {
+                let condition = self.expr(condition)?;
+                let otherwise_label = self.new_label();
+                let target = self.label(otherwise_label);
+                self.emit(6, &[condition, target]);
+                self.temps = 0;
+                self.block(then)?;
+                if otherwise.is_empty() {
+                    self.place(otherwise_label);
+                } else {
+                    let end = self.new_label();
+                    self.jump(end);
+                    self.place(otherwise_label);
+                    self.block(otherwise)?;
+                    self.place(end);
+                }
+            }
+            Stmt::While(condition, body) => {
+                let top = self.new_label();
+                let end = self.new_label();
+                self.place(top);
+                let condition = self.expr(condition)?;
+                let target = self.label(end);
+                self.emit(6, &[condition, target]);
+                self.temps = 0;
+                self.block(body)?;
+                self.jump(top);
+                self.place(end);
+            }
+            Stmt::Return(value) => {
+                let value = match value {
+                    Some(value) => self.expr(value)?,
+                    None => imm(0),
+                };
+                self.copy(value, Arg::Pos(Word::Global(RET, 0)));
+                self.emit(5, &[imm(1), rel(0)]);
+            }
+            Stmt::Expr(expr) => {
+                self.expr(expr)?;
+            }
+        }
+        Ok(())
+    }
+
+    // element of a global array at a constant index, which can be addressed directly
+    fn constant_element(&self, base: &Expr, index: &Expr) -> Result<Option<Arg>> {
+        if let (Expr::Var(name), Expr::Number(index)) = (base, index) {
+            if let Variable::GlobalArray(slot) = self.lookup(name)? {
+                return Ok(Some(Arg::Pos(Word::Global(slot, *index))));
+            }
+        }
+        Ok(None)
+    }
+
+    // evaluates base + index into a temporary
+    fn address(&mut self, base: &Expr, index: &Expr) -> Result<Arg> {
+        let mut base = self.expr(base)?;
+        if index.has_call() && !matches!(base, Arg::Imm(_)) {
+            let temp = self.temp();
+            self.copy(base, temp);
+            base = temp;
+        }
+        let index = self.expr(index)?;
+        let address = self.temp();
+        self.emit(1, &[base, index, address]);
+        Ok(address)
+    }
+
+    fn expr(&mut self, expr: &Expr) -> Result<Arg> {
+        match expr {
+            Expr::Number(value) => Ok(imm(*value)),
+            Expr::Var(name) => Ok(match self.lookup(name)? {
+                Variable::Local(slot) => rel(slot),
+                Variable::Global(slot) => Arg::Pos(Word::Global(slot, 0)),
+                Variable::GlobalArray(slot) => Arg::Imm(Word::Global(slot, 0)),
+                Variable::LocalArray(slot) => {
+                    let temp = self.temp();
+                    self.emit(1, &[Arg::Pos(Word::Global(FP, 0)), imm(slot), temp]);
+                    temp
+                }
+            }),
+            Expr::Index(base, index) => {
+                if let Some(element) = self.constant_element(base, index)? {
+                    return Ok(element);
+                }
+                let address = self.address(base, index)?;
+                // patch the source of the copy below with the computed address
+                let load = self.new_label();
+                self.copy(address, Arg::Pos(Word::Label(load, 1)));
+                self.place(load);
+                let temp = self.temp();
+                self.copy(Arg::Pos(Word::Value(0)), temp);
+                Ok(temp)
+            }
+            Expr::Call(name, args) => self.call(name, args),
+            Expr::Unary(op, operand) => {
+                let operand = self.expr(operand)?;
+                let temp = self.temp();
+                match op {
+                    UnOp::Neg => self.emit(2, &[operand, imm(-1), temp]),
+                    UnOp::Not => self.emit(8, &[operand, imm(0), temp]),
+                }
+                Ok(temp)
+            }
+            Expr::Binary(op @ (BinOp::And | BinOp::Or), left, right) => {
+                // short-circuits, the result starts out as the value that skipping gives
+                let is_and = *op == BinOp::And;
+                let temp = self.temp();
+                self.copy(imm(if is_and { 0 } else { 1 }), temp);
+                let left = self.expr(left)?;
+                let end = self.new_label();
+                let target = self.label(end);
+                self.emit(if is_and { 6 } else { 5 }, &[left, target]);
+                let right = self.expr(right)?;
+                self.emit(8, &[right, imm(0), temp]);
+                self.emit(8, &[temp, imm(0), temp]);
+                self.place(end);
+                Ok(temp)
+            }
+            Expr::Binary(op, left, right) => {
+                let mut left = self.expr(left)?;
+                if right.has_call() && !matches!(left, Arg::Imm(_)) {
+                    let temp = self.temp();
+                    self.copy(left, temp);
+                    left = temp;
+                }
+                let right = self.expr(right)?;
+                let temp = self.temp();
+                match op {
+                    BinOp::Add => self.emit(1, &[left, right, temp]),
+                    BinOp::Sub => {
+                        self.emit(2, &[right, imm(-1), temp]);
+                        self.emit(1, &[left, temp, temp]);
+                    }
+                    BinOp::Mul => self.emit(2, &[left, right, temp]),
+                    BinOp::Less => self.emit(7, &[left, right, temp]),
+                    BinOp::Greater => self.emit(7, &[right, left, temp]),
+                    BinOp::LessEqual => {
+                        self.emit(7, &[right, left, temp]);
+                        self.emit(8, &[temp, imm(0), temp]);
+                    }
+                    BinOp::GreaterEqual => {
+                        self.emit(7, &[left, right, temp]);
+                        self.emit(8, &[temp, imm(0), temp]);
+                    }
+                    BinOp::Equal => self.emit(8, &[left, right, temp]),
+                    BinOp::NotEqual => {
+                        self.emit(8, &[left, right, temp]);
+                        self.emit(8, &[temp, imm(0), temp]);
+                    }
+                    BinOp::And | BinOp::Or => unreachable!(),
+                }
+                Ok(temp)
+            }
+        }
+    }
+
+    fn call(&mut self, name: &str, args: &[Expr]) -> Result<Arg> {
+        let expected = match name {
+            "input" => 0,
+            "output" => 1,
+            _ => match self.functions.get(name) {
+                Some(function) => function.arity,
+                None => return Err(error(format!("undefined function {}", name))),
+            },
+        };
+        if args.len() != expected {
+            return Err(error(format!(
+                "function {} takes {} arguments, {} given in function {}",
+                name,
+                expected,
+                args.len(),
+                self.function_name
+            )));
+        }
+        if name == "input" {
+            let temp = self.temp();
+            self.emit(3, &[temp]);
+            return Ok(temp);
+        }
+        if name == "output" {
+            let value = self.expr(&args[0])?;
+            self.emit(4, &[value]);
+            return Ok(imm(0));
+        }
+
+        // arguments are evaluated into temporaries first, as nested calls use the same frame
+        let mut values = vec![];
+        for arg in args {
+            let value = self.expr(arg)?;
+            let temp = self.temp();
+            self.copy(value, temp);
+            values.push(temp);
+        }
+        let frame = self.current;
+        let callee = Arg::Rel(Word::Frame(frame, 1, 0));
+        let grow = Arg::Imm(Word::Frame(frame, 1, 0));
+        let shrink = Arg::Imm(Word::Frame(frame, -1, 0));
+        let fp = Arg::Pos(Word::Global(FP, 0));
+
+        let return_label = self.new_label();
+        let return_address = self.label(return_label);
+        self.copy(return_address, callee);
+        for (i, value) in values.into_iter().enumerate() {
+            self.copy(value, Arg::Rel(Word::Frame(frame, 1, i as i64 + 1)));
+        }
+        self.emit(9, &[grow]);
+        self.emit(1, &[fp, grow, fp]);
+        let function = self.functions[name].label;
+        self.jump(function);
+        self.place(return_label);
+        self.emit(9, &[shrink]);
+        self.emit(1, &[fp, shrink, fp]);
+        let temp = self.temp();
+        self.copy(Arg::Pos(Word::Global(RET, 0)), temp);
+        Ok(temp)
+    }
+
+    fn link(self) -> Vec<i64> {
+        let globals_start = self.words.len() as i64;
+        let mut program: Vec<i64> = self
+            .words
+            .iter()
+            .map(|word| match *word {
+                Word::Value(value) => value,
+                Word::Global(slot, offset) => globals_start + slot as i64 + offset,
+                Word::Label(label, offset) => self.labels[label].unwrap() as i64 + offset,
+                Word::Frame(function, scale, offset) => scale * self.frame_sizes[function] + offset,
+                Word::Stack => globals_start + self.global_values.len() as i64,
+            })
+            .collect();
+        program.extend(self.global_values.iter());
+        program
+    }
+}
+
+pub fn generate(program: &Program) -> Result<Vec<i64>> {
+    Codegen::default().program(program)
+}
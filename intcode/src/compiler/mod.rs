@@ -0,0 +1,111 @@
+// Compiler from a small C-like language to intcode. Programs are made of global variables
+// and functions:
+//
+//   var count = 0;           global scalar with a constant initial value
+//   var seen[100];           global array, zeroed
+//   fn add(a, b) { return a + b; }
+//   fn main() { var x = input(); output(add(x, 1)); }
+//
+// All values are integers. Statements are var (scalars and fixed size arrays, `var a[10];`),
+// assignment to variables and array elements, if/else, while, return and expressions.
+// Operators are + - * < > <= >= == != && || ! and unary -, comparisons give 0 or 1, and
+// && and || short-circuit. An array name evaluates to its address, so arrays can be passed
+// to functions and indexed there. input() reads a value and output(x) writes one.
+//
+// Every call gets a frame on a stack after the globals, addressed through the relative base:
+// slot 0 holds the return address, then come the parameters, the locals and temporaries.
+// Local variables start at 0 but local arrays aren't cleared. Array elements are reached by
+// writing the computed address into the following instruction, so compiled programs modify
+// their own code.
+
+mod codegen;
+mod parser;
+
+use std::io::Result;
+
+pub fn compile(source: &str) -> Result<Vec<i64>> {
+    codegen::generate(&parser::parse(source)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::compile;
+    use crate::{IntCodeMachine, HALTED};
+    use std::collections::VecDeque;
+
+    fn run(source: &str, input: &[i64]) -> Vec<i64> {
+        let mut machine = IntCodeMachine::new(compile(source).unwrap());
+        let (output, code) = machine.proceed_until_halt(VecDeque::from(input.to_vec()));
+        assert_eq!(code, HALTED, "{:?}", machine.error);
+        output.into()
+    }
+
+    #[test]
+    fn fibonacci() {
+        let output = run(include_str!("../../programs/fibonacci.ic"), &[10]);
+        assert_eq!(output, vec![0, 1, 1, 2, 3, 5, 8, 13, 21, 34]);
+    }
+
+    #[test]
+    fn factorial() {
+        let output = run(include_str!("../../programs/factorial.ic"), &[1, 5, 20, 0]);
+        assert_eq!(output, vec![1, 120, 2432902008176640000]);
+    }
+
+    #[test]
+    fn primes() {
+        let output = run(include_str!("../../programs/primes.ic"), &[30]);
+        assert_eq!(output, vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+    }
+
+    #[test]
+    fn sort() {
+        let output = run(
+            include_str!("../../programs/sort.ic"),
+            &[6, 5, -3, 9, 0, 5, 1],
+        );
+        assert_eq!(output, vec![-3, 0, 1, 5, 5, 9]);
+    }
+
+    #[test]
+    fn gcd() {
+        let output = run(include_str!("../../programs/gcd.ic"), &[48, 18, 17, 5, 0]);
+        assert_eq!(output, vec![6, 1]);
+    }
+
+    #[test]
+    fn hanoi() {
+        let output = run(include_str!("../../programs/hanoi.ic"), &[3]);
+        assert_eq!(output.len(), 15);
+        assert_eq!(&output[..4], &[1, 3, 1, 2]);
+        assert_eq!(output[14], 7);
+    }
+
+    #[test]
+    fn operators() {
+        let source = "
+            fn main() {
+                var a = input();
+                var b = input();
+                output(a - b);
+                output(-a * 2);
+                output(a < b);
+                output(a > b);
+                output(a <= a);
+                output(a >= b);
+                output(a != b);
+                output(!a);
+                output(a && 0);
+                output(0 || b);
+            }";
+        assert_eq!(run(source, &[3, 7]), vec![-4, -6, 1, 0, 1, 0, 1, 0, 0, 1]);
+    }
+
+    #[test]
+    fn errors() {
+        assert!(compile("fn f() {}").is_err());
+        assert!(compile("fn main() { output(x); }").is_err());
+        assert!(compile("fn f(a) {} fn main() { f(); }").is_err());
+        assert!(compile("fn main() { var x = ; }").is_err());
+    }
+}
@@ -0,0 +1,420 @@
+use std::io::{Error, ErrorKind, Result};
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(i64),
+    Ident(String),
+    Symbol(&'static str),
+    End,
+}
+
+const SYMBOLS: [&str; 21] = [
+    "<=", ">=", "==", "!=", "&&", "||", "(", ")", "{", "}", "[", "]", ",", ";", "=", "+", "-", "*",
+    "!", "<", ">",
+];
+
+const KEYWORDS: [&str; 6] = ["fn", "var", "if", "else", "while", "return"];
+
+fn error(line: usize, message: String) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("line {}: {}", line, message),
+    )
+}
+
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>> {
+    let mut tokens = vec![];
+    for (i, line) in source.lines().enumerate() {
+        let line_number = i + 1;
+        let line = line.split("//").next().unwrap();
+        let chars: Vec<char> = line.chars().collect();
+        let mut j = 0;
+        while j < chars.len() {
+            let c = chars[j];
+            if c.is_whitespace() {
+                j += 1;
+            } else if c.is_ascii_digit() {
+                let start = j;
+                while j < chars.len() && chars[j].is_ascii_digit() {
+                    j += 1;
+                }
+                let text: String = chars[start..j].iter().collect();
+                let value = text
+                    .parse()
+                    .map_err(|_| error(line_number, format!("number {} is too large", text)))?;
+                tokens.push((Token::Number(value), line_number));
+            } else if c.is_alphabetic() || c == '_' {
+                let start = j;
+                while j < chars.len() && (chars[j].is_alphanumeric() || chars[j] == '_') {
+                    j += 1;
+                }
+                tokens.push((Token::Ident(chars[start..j].iter().collect()), line_number));
+            } else {
+                let rest: String = chars[j..].iter().take(2).collect();
+                match SYMBOLS.iter().find(|s| rest.starts_with(**s)) {
+                    Some(symbol) => {
+                        tokens.push((Token::Symbol(symbol), line_number));
+                        j += symbol.len();
+                    }
+                    None => {
+                        return Err(error(line_number, format!("unexpected character {:?}", c)))
+                    }
+                }
+            }
+        }
+    }
+    let last_line = source.lines().count();
+    tokens.push((Token::End, last_line));
+    Ok(tokens)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Less,
+    Greater,
+    LessEqual,
+    GreaterEqual,
+    Equal,
+    NotEqual,
+    And,
+    Or,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnOp {
+    Neg,
+    Not,
+}
+
+#[derive(Debug)]
+pub enum Expr {
+    Number(i64),
+    Var(String),
+    Index(Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+    Unary(UnOp, Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    pub fn has_call(&self) -> bool {
+        match self {
+            Expr::Number(_) | Expr::Var(_) => false,
+            Expr::Call(_, _) => true,
+            Expr::Index(a, b) | Expr::Binary(_, a, b) => a.has_call() || b.has_call(),
+            Expr::Unary(_, a) => a.has_call(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Stmt {
+    Var(String, Option<Expr>),
+    Array(String, usize),
+    Assign(String, Expr),
+    AssignIndex(Expr, Expr, Expr),
+    If(Expr, Vec<Stmt>, Vec<Stmt>),
+    While(Expr, Vec<Stmt>),
+    Return(Option<Expr>),
+    Expr(Expr),
+}
+
+#[derive(Debug)]
+pub struct Function {
+    pub name: String,
+    pub params: Vec<String>,
+    pub body: Vec<Stmt>,
+}
+
+#[derive(Debug)]
+pub enum Global {
+    Scalar(String, i64),
+    Array(String, usize),
+}
+
+#[derive(Debug, Default)]
+pub struct Program {
+    pub globals: Vec<Global>,
+    pub functions: Vec<Function>,
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.position].0
+    }
+
+    fn line(&self) -> usize {
+        self.tokens[self.position].1
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.tokens[self.position].0.clone();
+        if token != Token::End {
+            self.position += 1;
+        }
+        token
+    }
+
+    fn unexpected<T>(&self, expected: &str) -> Result<T> {
+        Err(error(
+            self.line(),
+            format!("expected {}, found {:?}", expected, self.peek()),
+        ))
+    }
+
+    fn is_symbol(&self, symbol: &str) -> bool {
+        matches!(self.peek(), Token::Symbol(s) if *s == symbol)
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Token::Ident(s) if s == keyword)
+    }
+
+    fn eat(&mut self, symbol: &str) -> bool {
+        if self.is_symbol(symbol) {
+            self.next();
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, symbol: &str) -> Result<()> {
+        if !self.eat(symbol) {
+            return self.unexpected(&format!("{:?}", symbol));
+        }
+        Ok(())
+    }
+
+    fn ident(&mut self) -> Result<String> {
+        match self.peek().clone() {
+            Token::Ident(name) if !KEYWORDS.contains(&name.as_str()) => {
+                self.next();
+                Ok(name)
+            }
+            _ => self.unexpected("a name"),
+        }
+    }
+
+    fn number(&mut self) -> Result<i64> {
+        let negative = self.eat("-");
+        match *self.peek() {
+            Token::Number(value) => {
+                self.next();
+                Ok(if negative { -value } else { value })
+            }
+            _ => self.unexpected("a number"),
+        }
+    }
+
+    fn program(&mut self) -> Result<Program> {
+        let mut program = Program::default();
+        while *self.peek() != Token::End {
+            if self.is_keyword("var") {
+                self.next();
+                let name = self.ident()?;
+                if self.eat("[") {
+                    let size = self.number()?;
+                    self.expect("]")?;
+                    program
+                        .globals
+                        .push(Global::Array(name, size.max(0) as usize));
+                } else {
+                    let value = if self.eat("=") { self.number()? } else { 0 };
+                    program.globals.push(Global::Scalar(name, value));
+                }
+                self.expect(";")?;
+            } else if self.is_keyword("fn") {
+                self.next();
+                let name = self.ident()?;
+                self.expect("(")?;
+                let mut params = vec![];
+                while !self.eat(")") {
+                    if !params.is_empty() {
+                        self.expect(",")?;
+                    }
+                    params.push(self.ident()?);
+                }
+                let body = self.block()?;
+                program.functions.push(Function { name, params, body });
+            } else {
+                return self.unexpected("fn or var");
+            }
+        }
+        Ok(program)
+    }
+
+    fn block(&mut self) -> Result<Vec<Stmt>> {
+        self.expect("{")?;
+        let mut statements = vec![];
+        while !self.eat("}") {
+            statements.push(self.statement()?);
+        }
+        Ok(statements)
+    }
+
+    fn statement(&mut self) -> Result<Stmt> {
+        if self.is_keyword("var") {
+            self.next();
+            let name = self.ident()?;
+            let statement = if self.eat("[") {
+                let size = self.number()?;
+                self.expect("]")?;
+                Stmt::Array(name, size.max(0) as usize)
+            } else if self.eat("=") {
+                Stmt::Var(name, Some(self.expr()?))
+            } else {
+                Stmt::Var(name, None)
+            };
+            self.expect(";")?;
+            return Ok(statement);
+        }
+        if self.is_keyword("if") {
+            self.next();
+            self.expect("(")?;
+            let condition = self.expr()?;
+            self.expect(")")?;
+            let then = self.block()?;
+            let otherwise = if self.is_keyword("else") {
+                self.next();
+                if self.is_keyword("if") {
+                    vec![self.statement()?]
+                } else {
+                    self.block()?
+                }
+            } else {
+                vec![]
+            };
+            return Ok(Stmt::If(condition, then, otherwise));
+        }
+        if self.is_keyword("while") {
+            self.next();
+            self.expect("(")?;
+            let condition = self.expr()?;
+            self.expect(")")?;
+            return Ok(Stmt::While(condition, self.block()?));
+        }
+        if self.is_keyword("return") {
+            self.next();
+            let value = if self.is_symbol(";") {
+                None
+            } else {
+                Some(self.expr()?)
+            };
+            self.expect(";")?;
+            return Ok(Stmt::Return(value));
+        }
+
+        let expr = self.expr()?;
+        let statement = if self.eat("=") {
+            let value = self.expr()?;
+            match expr {
+                Expr::Var(name) => Stmt::Assign(name, value),
+                Expr::Index(base, index) => Stmt::AssignIndex(*base, *index, value),
+                _ => return Err(error(self.line(), "can't assign to expression".to_string())),
+            }
+        } else {
+            Stmt::Expr(expr)
+        };
+        self.expect(";")?;
+        Ok(statement)
+    }
+
+    fn expr(&mut self) -> Result<Expr> {
+        self.binary(0)
+    }
+
+    // operators by increasing precedence
+    fn binary(&mut self, level: usize) -> Result<Expr> {
+        const LEVELS: [&[(&str, BinOp)]; 5] = [
+            &[("||", BinOp::Or)],
+            &[("&&", BinOp::And)],
+            &[("==", BinOp::Equal), ("!=", BinOp::NotEqual)],
+            &[
+                ("<=", BinOp::LessEqual),
+                (">=", BinOp::GreaterEqual),
+                ("<", BinOp::Less),
+                (">", BinOp::Greater),
+            ],
+            &[("+", BinOp::Add), ("-", BinOp::Sub)],
+        ];
+        if level == LEVELS.len() {
+            return self.product();
+        }
+        let mut left = self.binary(level + 1)?;
+        'outer: loop {
+            for (symbol, op) in LEVELS[level] {
+                if self.eat(symbol) {
+                    let right = self.binary(level + 1)?;
+                    left = Expr::Binary(*op, Box::new(left), Box::new(right));
+                    continue 'outer;
+                }
+            }
+            return Ok(left);
+        }
+    }
+
+    fn product(&mut self) -> Result<Expr> {
+        let mut left = self.unary()?;
+        while self.eat("*") {
+            let right = self.unary()?;
+            left = Expr::Binary(BinOp::Mul, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        if self.eat("-") {
+            return Ok(Expr::Unary(UnOp::Neg, Box::new(self.unary()?)));
+        }
+        if self.eat("!") {
+            return Ok(Expr::Unary(UnOp::Not, Box::new(self.unary()?)));
+        }
+        let mut expr = self.primary()?;
+        while self.eat("[") {
+            let index = self.expr()?;
+            self.expect("]")?;
+            expr = Expr::Index(Box::new(expr), Box::new(index));
+        }
+        Ok(expr)
+    }
+
+    fn primary(&mut self) -> Result<Expr> {
+        if self.eat("(") {
+            let expr = self.expr()?;
+            self.expect(")")?;
+            return Ok(expr);
+        }
+        if let Token::Number(value) = *self.peek() {
+            self.next();
+            return Ok(Expr::Number(value));
+        }
+        let name = self.ident()?;
+        if self.eat("(") {
+            let mut args = vec![];
+            while !self.eat(")") {
+                if !args.is_empty() {
+                    self.expect(",")?;
+                }
+                args.push(self.expr()?);
+            }
+            return Ok(Expr::Call(name, args));
+        }
+        Ok(Expr::Var(name))
+    }
+}
+
+pub fn parse(source: &str) -> Result<Program> {
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        position: 0,
+    };
+    parser.program()
+}
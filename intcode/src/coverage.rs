@@ -0,0 +1,248 @@
+use std::fmt::Write;
+
+use crate::disasm::{self, Instruction};
+use crate::Observer;
+
+// per-address access counts, attach the same Rc<RefCell<Coverage>> to several machines
+// to aggregate their runs
+#[derive(Clone, Default)]
+pub struct Coverage {
+    pub program: Vec<i64>,
+    // times each address was the first word of an executed instruction
+    pub executed: Vec<u64>,
+    // times each address was fetched as part of an executed instruction
+    pub fetched: Vec<u64>,
+    pub reads: Vec<u64>,
+    pub writes: Vec<u64>,
+}
+
+enum Line {
+    Executed(Instruction),
+    Unexecuted(Instruction),
+    Data(usize),
+}
+
+impl Coverage {
+    pub fn new(program: &[i64]) -> Coverage {
+        let mut coverage = Coverage {
+            program: program.to_vec(),
+            ..Coverage::default()
+        };
+        coverage.grow(program.len().saturating_sub(1));
+        coverage
+    }
+
+    fn grow(&mut self, address: usize) {
+        if address >= self.executed.len() {
+            self.executed.resize(address + 1, 0);
+            self.fetched.resize(address + 1, 0);
+            self.reads.resize(address + 1, 0);
+            self.writes.resize(address + 1, 0);
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        let size = self.program.len();
+        let count = |counts: &[u64]| counts[..size].iter().filter(|c| **c > 0).count();
+        let percentage = |n: usize| 100.0 * n as f64 / size.max(1) as f64;
+
+        let executed = count(&self.fetched);
+        let instructions = count(&self.executed);
+        let read = count(&self.reads);
+        let written = count(&self.writes);
+        let untouched = (0..size)
+            .filter(|a| self.fetched[*a] == 0 && self.reads[*a] == 0 && self.writes[*a] == 0)
+            .count();
+        let beyond = (size..self.executed.len())
+            .filter(|a| self.reads[*a] > 0 || self.writes[*a] > 0)
+            .count();
+        let unexecuted_code: usize = self
+            .lines()
+            .iter()
+            .map(|line| match line {
+                Line::Unexecuted(instruction) => instruction.size(),
+                _ => 0,
+            })
+            .sum();
+
+        let mut summary = String::new();
+        writeln!(summary, "program size:      {} words", size).unwrap();
+        writeln!(
+            summary,
+            "executed as code:  {} words ({:.1}%) in {} distinct instructions",
+            executed,
+            percentage(executed),
+            instructions
+        )
+        .unwrap();
+        writeln!(
+            summary,
+            "unexecuted code:   {} words ({:.1}%)",
+            unexecuted_code,
+            percentage(unexecuted_code)
+        )
+        .unwrap();
+        writeln!(
+            summary,
+            "read as data:      {} words ({:.1}%)",
+            read,
+            percentage(read)
+        )
+        .unwrap();
+        writeln!(
+            summary,
+            "written:           {} words ({:.1}%)",
+            written,
+            percentage(written)
+        )
+        .unwrap();
+        writeln!(
+            summary,
+            "never touched:     {} words ({:.1}%)",
+            untouched,
+            percentage(untouched)
+        )
+        .unwrap();
+        writeln!(summary, "beyond program:    {} addresses accessed", beyond).unwrap();
+        summary
+    }
+
+    // splits the program into executed instructions, instructions that were never executed
+    // and data, unexecuted words are only treated as code if they decode as an instruction
+    fn lines(&self) -> Vec<Line> {
+        let mut lines = vec![];
+        let mut position = 0;
+        while position < self.program.len() {
+            if self.executed[position] > 0 {
+                if let Some(instruction) = disasm::decode(&self.program, position) {
+                    position += instruction.size();
+                    lines.push(Line::Executed(instruction));
+                    continue;
+                }
+            } else if self.fetched[position] == 0 {
+                if let Some(instruction) = disasm::decode(&self.program, position) {
+                    let mut span = position..position + instruction.size();
+                    if span.all(|a| self.fetched[a] == 0 && self.reads[a] == 0) {
+                        position += instruction.size();
+                        lines.push(Line::Unexecuted(instruction));
+                        continue;
+                    }
+                }
+            }
+            lines.push(Line::Data(position));
+            position += 1;
+        }
+        lines
+    }
+
+    fn counts(&self, address: usize) -> (u64, u64, u64) {
+        (
+            self.executed[address],
+            self.reads[address],
+            self.writes[address],
+        )
+    }
+
+    pub fn listing(&self) -> String {
+        let mut listing = String::from("   addr       exec     read    write\n");
+        for line in self.lines() {
+            let (marker, address, text) = match line {
+                Line::Executed(instruction) => (' ', instruction.position, instruction.to_string()),
+                Line::Unexecuted(instruction) => (
+                    '!',
+                    instruction.position,
+                    format!("{}    ; never executed", instruction),
+                ),
+                Line::Data(address) => (' ', address, format!("data {}", self.program[address])),
+            };
+            let (executed, read, written) = self.counts(address);
+            writeln!(
+                listing,
+                "{} {:>5} {:>10} {:>8} {:>8}  {}",
+                marker, address, executed, read, written, text
+            )
+            .unwrap();
+        }
+        listing
+    }
+
+    pub fn html(&self) -> String {
+        let mut html = String::from(
+            "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>Intcode coverage</title>
+<style>
+body { font-family: monospace; }
+table { border-collapse: collapse; }
+td, th { padding: 1px 8px; text-align: right; }
+td.text { text-align: left; }
+tr.executed { background: #b6f2b6; }
+tr.unexecuted { background: #f5a9a9; }
+tr.written { background: #f7d08a; }
+tr.read { background: #a9cdf5; }
+tr.untouched { background: #e0e0e0; }
+</style>
+</head>
+<body>
+<pre>",
+        );
+        html.push_str(&self.summary());
+        html.push_str(
+            "</pre>
+<table>
+<tr><th>addr</th><th>exec</th><th>read</th><th>write</th><th></th></tr>
+",
+        );
+        for line in self.lines() {
+            let (class, address, text) = match line {
+                Line::Executed(instruction) => {
+                    ("executed", instruction.position, instruction.to_string())
+                }
+                Line::Unexecuted(instruction) => {
+                    ("unexecuted", instruction.position, instruction.to_string())
+                }
+                Line::Data(address) => {
+                    let class = if self.writes[address] > 0 {
+                        "written"
+                    } else if self.reads[address] > 0 {
+                        "read"
+                    } else {
+                        "untouched"
+                    };
+                    (class, address, format!("data {}", self.program[address]))
+                }
+            };
+            let (executed, read, written) = self.counts(address);
+            writeln!(
+                html,
+                "<tr class=\"{}\"><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td class=\"text\">{}</td></tr>",
+                class, address, executed, read, written, text
+            )
+            .unwrap();
+        }
+        html.push_str("</table>\n</body>\n</html>\n");
+        html
+    }
+}
+
+impl Observer for Coverage {
+    fn on_execute(&mut self, position: usize, len: usize) {
+        self.grow(position + len - 1);
+        self.executed[position] += 1;
+        for address in position..position + len {
+            self.fetched[address] += 1;
+        }
+    }
+
+    fn on_read(&mut self, address: usize) {
+        self.grow(address);
+        self.reads[address] += 1;
+    }
+
+    fn on_write(&mut self, address: usize, _value: i64) {
+        self.grow(address);
+        self.writes[address] += 1;
+    }
+}
@@ -0,0 +1,304 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{Error, ErrorKind, Result};
+use std::rc::Rc;
+
+use crate::{IntCodeMachine, AWAITING_INPUT, HALTED, INVALID_OPCODE};
+
+// screen tiles
+pub const EMPTY: i64 = 0;
+pub const WALL: i64 = 1;
+pub const BLOCK: i64 = 2;
+pub const PADDLE: i64 = 3;
+pub const BALL: i64 = 4;
+
+pub trait Device {
+    // number of consecutive outputs making up one message for this device, 0 if it takes no output
+    fn frame_len(&self) -> usize {
+        0
+    }
+
+    // returns false to offer the frame to the next device on the bus instead
+    fn write(&mut self, _frame: &[i64]) -> bool {
+        false
+    }
+
+    // value for the next input instruction, None if this device has nothing to offer
+    fn read(&mut self) -> Option<i64> {
+        None
+    }
+}
+
+// lets the caller keep a handle on a device after attaching it to a bus
+impl<D: Device> Device for Rc<RefCell<D>> {
+    fn frame_len(&self) -> usize {
+        self.borrow().frame_len()
+    }
+
+    fn write(&mut self, frame: &[i64]) -> bool {
+        self.borrow_mut().write(frame)
+    }
+
+    fn read(&mut self) -> Option<i64> {
+        self.borrow_mut().read()
+    }
+}
+
+#[derive(Default)]
+pub struct DeviceBus {
+    devices: Vec<Box<dyn Device>>,
+    pending: Vec<i64>,
+}
+
+impl DeviceBus {
+    pub fn new() -> DeviceBus {
+        DeviceBus::default()
+    }
+
+    // devices are asked for input and offered output frames in the order they were attached
+    pub fn attach<D: Device + 'static>(&mut self, device: D) {
+        self.devices.push(Box::new(device));
+    }
+
+    // runs the machine until it halts or asks for input no device can supply
+    pub fn run(&mut self, machine: &mut IntCodeMachine) -> Result<i64> {
+        let mut code = self.resume(machine, VecDeque::new())?;
+        while code == AWAITING_INPUT {
+            match self.read() {
+                Some(value) => code = self.resume(machine, VecDeque::from([value]))?,
+                None => break,
+            }
+        }
+        Ok(code)
+    }
+
+    // queues the next value the devices offer, then runs until the following input request
+    pub fn tick(&mut self, machine: &mut IntCodeMachine) -> Result<i64> {
+        let input: VecDeque<i64> = self.read().into_iter().collect();
+        self.resume(machine, input)
+    }
+
+    fn resume(&mut self, machine: &mut IntCodeMachine, input: VecDeque<i64>) -> Result<i64> {
+        let (output, code) = machine.proceed_until_halt(input);
+        for value in output {
+            self.dispatch(value)?;
+        }
+        if code == INVALID_OPCODE {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                machine.error.clone().unwrap_or_default(),
+            ));
+        }
+        if code == HALTED && !self.pending.is_empty() {
+            return Err(Error::new(
+                ErrorKind::UnexpectedEof,
+                format!("Program halted mid-frame: {:?}", self.pending),
+            ));
+        }
+        Ok(code)
+    }
+
+    fn read(&mut self) -> Option<i64> {
+        self.devices.iter_mut().find_map(|device| device.read())
+    }
+
+    fn dispatch(&mut self, value: i64) -> Result<()> {
+        self.pending.push(value);
+        let mut longest_frame = 0;
+        for device in self.devices.iter_mut() {
+            let frame_len = device.frame_len();
+            longest_frame = longest_frame.max(frame_len);
+            if frame_len == self.pending.len() && device.write(&self.pending) {
+                self.pending.clear();
+                return Ok(());
+            }
+        }
+        if self.pending.len() >= longest_frame {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("No device accepted output frame: {:?}", self.pending),
+            ));
+        }
+        Ok(())
+    }
+}
+
+// arcade screen, takes (x, y, tile) triples, with (-1, 0, score) updating the segment display
+#[derive(Default)]
+pub struct Screen {
+    pub tiles: HashMap<(i64, i64), i64>,
+    pub score: Option<i64>,
+    // last positions the ball and paddle were drawn at
+    pub ball: Option<(i64, i64)>,
+    pub paddle: Option<(i64, i64)>,
+}
+
+impl Screen {
+    pub fn new() -> Screen {
+        Screen::default()
+    }
+
+    pub fn count(&self, tile: i64) -> usize {
+        self.tiles.values().filter(|t| **t == tile).count()
+    }
+
+    pub fn find(&self, tile: i64) -> Option<(i64, i64)> {
+        self.tiles
+            .iter()
+            .find(|(_, t)| **t == tile)
+            .map(|(position, _)| *position)
+    }
+
+    // draws every tile with the given glyph, missing tiles as EMPTY, ending rows with line_end
+    pub fn render<'a>(&self, glyph: impl Fn(i64) -> &'a str, line_end: &str) -> String {
+        let max_x = self.tiles.keys().map(|(x, _)| *x).max().unwrap_or(0);
+        let max_y = self.tiles.keys().map(|(_, y)| *y).max().unwrap_or(0);
+        let mut rendered = String::new();
+        for y in 0..=max_y {
+            for x in 0..=max_x {
+                rendered += glyph(*self.tiles.get(&(x, y)).unwrap_or(&EMPTY));
+            }
+            rendered += line_end;
+        }
+        rendered
+    }
+}
+
+impl Device for Screen {
+    fn frame_len(&self) -> usize {
+        3
+    }
+
+    fn write(&mut self, frame: &[i64]) -> bool {
+        if frame[0] == -1 && frame[1] == 0 {
+            self.score = Some(frame[2]);
+        } else {
+            let position = (frame[0], frame[1]);
+            match frame[2] {
+                BALL => self.ball = Some(position),
+                PADDLE => self.paddle = Some(position),
+                _ => {}
+            }
+            self.tiles.insert(position, frame[2]);
+        }
+        true
+    }
+}
+
+// answers every input request with -1 (left), 0 (neutral) or 1 (right) from the controller
+pub struct Joystick {
+    controller: Box<dyn FnMut() -> i64>,
+}
+
+impl Joystick {
+    pub fn new<F: FnMut() -> i64 + 'static>(controller: F) -> Joystick {
+        Joystick {
+            controller: Box::new(controller),
+        }
+    }
+}
+
+impl Device for Joystick {
+    fn read(&mut self) -> Option<i64> {
+        Some((self.controller)())
+    }
+}
+
+// hull-painting robot, its camera reports the colour of the current panel (0 black, 1 white)
+// and it takes (colour, turn) pairs, turning left on 0 and right on 1 before moving forward
+#[derive(Default)]
+pub struct HullRobot {
+    pub panels: HashMap<(i64, i64), i64>,
+    pub painted: HashSet<(i64, i64)>,
+    pub position: (i64, i64),
+    // unit vector, y grows downwards so up is (0, -1)
+    pub direction: (i64, i64),
+}
+
+impl HullRobot {
+    pub fn new(starting_colour: i64) -> HullRobot {
+        HullRobot {
+            panels: HashMap::from([((0, 0), starting_colour)]),
+            painted: HashSet::new(),
+            position: (0, 0),
+            direction: (0, -1),
+        }
+    }
+}
+
+impl Device for HullRobot {
+    fn frame_len(&self) -> usize {
+        2
+    }
+
+    fn write(&mut self, frame: &[i64]) -> bool {
+        self.panels.insert(self.position, frame[0]);
+        self.painted.insert(self.position);
+        let (dx, dy) = self.direction;
+        self.direction = if frame[1] == 0 { (dy, -dx) } else { (-dy, dx) };
+        self.position = (
+            self.position.0 + self.direction.0,
+            self.position.1 + self.direction.1,
+        );
+        true
+    }
+
+    fn read(&mut self) -> Option<i64> {
+        Some(*self.panels.get(&self.position).unwrap_or(&0))
+    }
+}
+
+// network interface: first reports its address, then delivers queued packets as x, y pairs,
+// reading -1 whenever the inbox is empty; outgoing (destination, x, y) packets go to the outbox.
+// It never runs out of input, so drive it with DeviceBus::tick and move packets between cards
+// in between rather than using run
+pub struct NetworkCard {
+    pub address: i64,
+    pub inbox: VecDeque<i64>,
+    pub outbox: VecDeque<(i64, i64, i64)>,
+    pub idle_reads: usize,
+    booted: bool,
+}
+
+impl NetworkCard {
+    pub fn new(address: i64) -> NetworkCard {
+        NetworkCard {
+            address,
+            inbox: VecDeque::new(),
+            outbox: VecDeque::new(),
+            idle_reads: 0,
+            booted: false,
+        }
+    }
+
+    pub fn deliver(&mut self, x: i64, y: i64) {
+        self.inbox.push_back(x);
+        self.inbox.push_back(y);
+        self.idle_reads = 0;
+    }
+}
+
+impl Device for NetworkCard {
+    fn frame_len(&self) -> usize {
+        3
+    }
+
+    fn write(&mut self, frame: &[i64]) -> bool {
+        self.outbox.push_back((frame[0], frame[1], frame[2]));
+        true
+    }
+
+    fn read(&mut self) -> Option<i64> {
+        if !self.booted {
+            self.booted = true;
+            return Some(self.address);
+        }
+        match self.inbox.pop_front() {
+            Some(value) => Some(value),
+            None => {
+                self.idle_reads += 1;
+                Some(-1)
+            }
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::{disasm, IntCodeMachine, Observer, AWAITING_INPUT, HALTED, INVALID_OPCODE, TRAPPED};
+
+#[derive(Default)]
+struct WriteLog {
+    writes: Vec<(usize, i64)>,
+}
+
+impl Observer for WriteLog {
+    fn on_write(&mut self, address: usize, value: i64) {
+        self.writes.push((address, value));
+    }
+}
+
+// what one machine did during a single step; position is where the instruction was, while
+// next_position and relative_base are the state the step left behind
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StepState {
+    pub position: usize,
+    pub next_position: usize,
+    pub relative_base: i64,
+    pub instruction: String,
+    // (address, new value)
+    pub writes: Vec<(usize, i64)>,
+    pub input: Option<i64>,
+    pub output: Option<i64>,
+    // stop code if the machine didn't proceed past this step
+    pub code: Option<i64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DivergenceKind {
+    Position,
+    RelativeBase,
+    Writes,
+    Output,
+    Status,
+}
+
+pub struct Divergence {
+    // index of the first step whose instruction left the two machines in different states
+    pub step: u64,
+    pub kind: DivergenceKind,
+    pub left: StepState,
+    pub right: StepState,
+    // the identical steps leading up to the divergence, oldest first
+    pub context: Vec<StepState>,
+}
+
+pub enum DiffResult {
+    // both runs stopped the same way after this many steps
+    Identical(u64),
+    // both runs were still identical when the step limit was reached
+    StepLimit(u64),
+    Diverged(Box<Divergence>),
+}
+
+struct Side {
+    machine: IntCodeMachine,
+    input: VecDeque<i64>,
+    log: Rc<RefCell<WriteLog>>,
+}
+
+impl Side {
+    fn new(mut machine: IntCodeMachine, input: VecDeque<i64>) -> Side {
+        let log = Rc::new(RefCell::new(WriteLog::default()));
+        machine.observe(log.clone());
+        Side {
+            machine,
+            input,
+            log,
+        }
+    }
+
+    fn step(&mut self) -> StepState {
+        let position = self.machine.position;
+        let instruction = match disasm::decode(&self.machine.instructions, position) {
+            Some(instruction) => instruction.to_string(),
+            None => format!("{:?}", self.machine.instructions.get(position)),
+        };
+        let is_input = self.machine.instructions.get(position).map(|i| i % 100) == Some(3);
+        let mut output = VecDeque::new();
+        let code = self.machine.step(&mut self.input, &mut output);
+        let mut writes: Vec<(usize, i64)> = self.log.borrow_mut().writes.drain(..).collect();
+        // differing inputs are expected, what matters is where they make execution differ
+        let input = if is_input {
+            writes.pop().map(|(_, value)| value)
+        } else {
+            None
+        };
+        StepState {
+            position,
+            next_position: self.machine.position,
+            relative_base: self.machine.relative_base,
+            instruction,
+            writes,
+            input,
+            output: output.pop_front(),
+            code,
+        }
+    }
+}
+
+// runs both machines in lockstep until they diverge, both stop or max_steps is reached,
+// keeping up to `context` identical steps to show before the divergence
+pub fn diff_runs(
+    left: IntCodeMachine,
+    left_input: VecDeque<i64>,
+    right: IntCodeMachine,
+    right_input: VecDeque<i64>,
+    context: usize,
+    max_steps: u64,
+) -> DiffResult {
+    let mut left = Side::new(left, left_input);
+    let mut right = Side::new(right, right_input);
+    let mut previous: VecDeque<StepState> = VecDeque::new();
+    let mut step = 0;
+    loop {
+        if step == max_steps {
+            return DiffResult::StepLimit(step);
+        }
+        let left_state = left.step();
+        let right_state = right.step();
+
+        let kind = if left_state.position != right_state.position
+            || left_state.next_position != right_state.next_position
+        {
+            Some(DivergenceKind::Position)
+        } else if left_state.code != right_state.code {
+            Some(DivergenceKind::Status)
+        } else if left_state.relative_base != right_state.relative_base {
+            Some(DivergenceKind::RelativeBase)
+        } else if left_state.writes != right_state.writes {
+            Some(DivergenceKind::Writes)
+        } else if left_state.output != right_state.output {
+            Some(DivergenceKind::Output)
+        } else {
+            None
+        };
+
+        if let Some(kind) = kind {
+            return DiffResult::Diverged(Box::new(Divergence {
+                step,
+                kind,
+                left: left_state,
+                right: right_state,
+                context: previous.into(),
+            }));
+        }
+        if left_state.code.is_some() {
+            return DiffResult::Identical(step);
+        }
+
+        previous.push_back(left_state);
+        if previous.len() > context {
+            previous.pop_front();
+        }
+        step += 1;
+    }
+}
+
+fn describe_code(code: Option<i64>) -> &'static str {
+    match code {
+        None => "running",
+        Some(HALTED) => "halted",
+        Some(INVALID_OPCODE) => "invalid opcode",
+        Some(AWAITING_INPUT) => "awaiting input",
+        Some(TRAPPED) => "trapped",
+        Some(_) => "stopped",
+    }
+}
+
+impl fmt::Display for StepState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:>6}  {:<28} next={} rb={}",
+            self.position, self.instruction, self.next_position, self.relative_base
+        )?;
+        for (address, value) in self.writes.iter() {
+            write!(f, "  [{}]<-{}", address, value)?;
+        }
+        if let Some(input) = self.input {
+            write!(f, "  in {}", input)?;
+        }
+        if let Some(output) = self.output {
+            write!(f, "  out {}", output)?;
+        }
+        if self.code.is_some() {
+            write!(f, "  ({})", describe_code(self.code))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Runs diverged at step {} ({:?})", self.step, self.kind)?;
+        for state in self.context.iter() {
+            writeln!(f, "  {}", state)?;
+        }
+        writeln!(f, "< {}", self.left)?;
+        writeln!(f, "> {}", self.right)
+    }
+}
+
+impl fmt::Display for DiffResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DiffResult::Identical(steps) => writeln!(f, "Runs identical for {} steps", steps),
+            DiffResult::StepLimit(steps) => {
+                writeln!(f, "Runs identical until the limit of {} steps", steps)
+            }
+            DiffResult::Diverged(divergence) => write!(f, "{}", divergence),
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use std::fmt;
+
+use crate::isa;
+
+pub struct Instruction {
+    pub position: usize,
+    pub opcode: i64,
+    // (mode, raw parameter) pairs
+    pub params: Vec<(i64, i64)>,
+}
+
+impl Instruction {
+    // number of words the instruction takes up
+    pub fn size(&self) -> usize {
+        self.params.len() + 1
+    }
+}
+
+// decodes the built-in instruction at position, None if the word there isn't one
+pub fn decode(memory: &[i64], position: usize) -> Option<Instruction> {
+    let instruction = *memory.get(position)?;
+    if instruction < 0 {
+        return None;
+    }
+    let opcode = instruction % 100;
+    let arity = isa::arity(opcode)?;
+    if position + arity >= memory.len() {
+        return None;
+    }
+    let mut params = vec![];
+    for i in 1..=arity {
+        let mode = instruction / 10_i64.pow((i + 1).try_into().unwrap()) % 10;
+        if mode > 2 {
+            return None;
+        }
+        params.push((mode, memory[position + i]));
+    }
+    // leftover digits mean this is more likely data than code
+    if instruction / 10_i64.pow((arity + 2).try_into().unwrap()) != 0 {
+        return None;
+    }
+    Some(Instruction {
+        position,
+        opcode,
+        params,
+    })
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", isa::mnemonic(self.opcode).unwrap_or("???"))?;
+        for (i, (mode, value)) in self.params.iter().enumerate() {
+            write!(f, "{}", if i == 0 { " " } else { ", " })?;
+            match mode {
+                0 => write!(f, "[{}]", value)?,
+                1 => write!(f, "{}", value)?,
+                _ => write!(f, "[rb{:+}]", value)?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,184 @@
+use std::collections::VecDeque;
+use std::io::{Error, ErrorKind, Result};
+use std::mem::size_of;
+
+use crate::IntCodeMachine;
+
+// everything needed to undo a single executed instruction
+#[derive(Clone, Debug)]
+pub struct StepRecord {
+    pub position: usize,
+    pub relative_base: i64,
+    pub exit_code: Option<i64>,
+    // length of memory before the step, anything beyond it was grown by the step
+    pub memory_len: usize,
+    // (address, previous value) in the order the writes happened
+    pub writes: Vec<(usize, i64)>,
+    pub input: Option<i64>,
+    pub output: Option<i64>,
+    // the writes came from a patch rather than executing an instruction
+    pub patched: bool,
+}
+
+impl StepRecord {
+    pub(crate) fn start(machine: &IntCodeMachine) -> StepRecord {
+        StepRecord {
+            position: machine.position,
+            relative_base: machine.relative_base,
+            exit_code: machine.exit_code,
+            memory_len: machine.instructions.len(),
+            writes: vec![],
+            input: None,
+            output: None,
+            patched: false,
+        }
+    }
+
+    pub(crate) fn changed(&self, machine: &IntCodeMachine) -> bool {
+        self.position != machine.position
+            || self.relative_base != machine.relative_base
+            || self.exit_code != machine.exit_code
+            || self.memory_len != machine.instructions.len()
+            || !self.writes.is_empty()
+            || self.input.is_some()
+            || self.output.is_some()
+    }
+
+    fn size(&self) -> usize {
+        size_of::<StepRecord>() + self.writes.len() * size_of::<(usize, i64)>()
+    }
+}
+
+// undo log of executed instructions and applied patches, dropping the oldest records once over budget
+pub struct History {
+    records: VecDeque<StepRecord>,
+    budget: usize,
+    used: usize,
+    // number of steps currently executed, counting ones whose records were dropped and patches
+    steps: u64,
+}
+
+impl History {
+    // budget is the approximate number of bytes the records may take up
+    pub fn new(budget: usize) -> History {
+        History {
+            records: VecDeque::new(),
+            budget,
+            used: 0,
+            steps: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    // earliest step that can still be rewound to
+    pub fn oldest_step(&self) -> u64 {
+        self.steps - self.records.len() as u64
+    }
+
+    pub(crate) fn push(&mut self, record: StepRecord) {
+        self.used += record.size();
+        self.records.push_back(record);
+        self.steps += 1;
+        while self.used > self.budget {
+            match self.records.pop_front() {
+                Some(dropped) => self.used -= dropped.size(),
+                None => break,
+            }
+        }
+    }
+
+    fn pop(&mut self) -> Option<StepRecord> {
+        let record = self.records.pop_back()?;
+        self.used -= record.size();
+        self.steps -= 1;
+        Some(record)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Checkpoint(pub u64);
+
+impl IntCodeMachine {
+    pub fn enable_history(&mut self, budget: usize) {
+        self.history = Some(History::new(budget));
+    }
+
+    pub fn disable_history(&mut self) {
+        self.history = None;
+        self.rewound_input.clear();
+    }
+
+    // undoes the last executed instruction, returning what it did
+    pub fn step_back(&mut self) -> Option<StepRecord> {
+        let record = self.history.as_mut()?.pop()?;
+        for (address, value) in record.writes.iter().rev() {
+            self.instructions[*address] = *value;
+        }
+        self.instructions.truncate(record.memory_len);
+        self.position = record.position;
+        self.relative_base = record.relative_base;
+        self.exit_code = record.exit_code;
+        if let Some(value) = record.input {
+            self.rewound_input.push_front(value);
+        }
+        Some(record)
+    }
+
+    // steps back until the instruction that last wrote to address is the next to execute
+    pub fn run_back_to_write(&mut self, address: usize) -> Option<StepRecord> {
+        let history = self.history.as_ref()?;
+        if !history
+            .records
+            .iter()
+            .any(|r| r.writes.iter().any(|(a, _)| *a == address))
+        {
+            return None;
+        }
+        loop {
+            let record = self.step_back()?;
+            if record.writes.iter().any(|(a, _)| *a == address) {
+                return Some(record);
+            }
+        }
+    }
+
+    pub fn checkpoint(&self) -> Option<Checkpoint> {
+        Some(Checkpoint(self.history.as_ref()?.steps))
+    }
+
+    pub fn rewind_to(&mut self, checkpoint: Checkpoint) -> Result<()> {
+        let (oldest, steps) = match self.history.as_ref() {
+            Some(history) => (history.oldest_step(), history.steps),
+            None => {
+                return Err(Error::new(
+                    ErrorKind::Unsupported,
+                    "History is not enabled on this machine",
+                ))
+            }
+        };
+        if checkpoint.0 < oldest || checkpoint.0 > steps {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!(
+                    "Checkpoint at step {} is outside the recorded history (steps {} to {})",
+                    checkpoint.0, oldest, steps
+                ),
+            ));
+        }
+        for _ in checkpoint.0..steps {
+            self.step_back();
+        }
+        Ok(())
+    }
+}
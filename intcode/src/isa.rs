@@ -0,0 +1,111 @@
+use std::fmt;
+
+// revisions of the instruction set, as introduced by the puzzles
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IsaProfile {
+    // add, multiply and halt, position mode only
+    Day2,
+    // adds input/output, jumps and comparisons, plus immediate mode
+    Day5,
+    // adds relative mode and relative base adjustment
+    #[default]
+    Day9,
+}
+
+impl fmt::Display for IsaProfile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IsaProfile::Day2 => write!(f, "day 2"),
+            IsaProfile::Day5 => write!(f, "day 5"),
+            IsaProfile::Day9 => write!(f, "day 9"),
+        }
+    }
+}
+
+// (mnemonic, full name) of a built-in opcode
+fn names(opcode: i64) -> Option<(&'static str, &'static str)> {
+    match opcode {
+        1 => Some(("add", "add")),
+        2 => Some(("mul", "multiply")),
+        3 => Some(("in", "input")),
+        4 => Some(("out", "output")),
+        5 => Some(("jt", "jump-if-true")),
+        6 => Some(("jf", "jump-if-false")),
+        7 => Some(("lt", "less-than")),
+        8 => Some(("eq", "equals")),
+        9 => Some(("arb", "adjust relative base")),
+        99 => Some(("hlt", "halt")),
+        _ => None,
+    }
+}
+
+pub fn opcode_name(opcode: i64) -> Option<&'static str> {
+    names(opcode).map(|(_, name)| name)
+}
+
+pub fn mnemonic(opcode: i64) -> Option<&'static str> {
+    names(opcode).map(|(mnemonic, _)| mnemonic)
+}
+
+// number of parameters of a built-in opcode
+pub fn arity(opcode: i64) -> Option<usize> {
+    match opcode {
+        1 | 2 | 7 | 8 => Some(3),
+        5 | 6 => Some(2),
+        3 | 4 | 9 => Some(1),
+        99 => Some(0),
+        _ => None,
+    }
+}
+
+// indexes of the parameters a built-in opcode writes to
+pub fn write_targets(opcode: i64) -> &'static [usize] {
+    match opcode {
+        1 | 2 | 7 | 8 => &[2],
+        3 => &[0],
+        _ => &[],
+    }
+}
+
+impl IsaProfile {
+    pub fn allows_opcode(&self, opcode: i64) -> bool {
+        match self {
+            IsaProfile::Day2 => matches!(opcode, 1 | 2 | 99),
+            IsaProfile::Day5 => matches!(opcode, 1..=8 | 99),
+            IsaProfile::Day9 => matches!(opcode, 1..=9 | 99),
+        }
+    }
+
+    pub fn allows_mode(&self, mode: i64) -> bool {
+        match self {
+            IsaProfile::Day2 => mode == 0,
+            IsaProfile::Day5 => matches!(mode, 0 | 1),
+            IsaProfile::Day9 => matches!(mode, 0..=2),
+        }
+    }
+
+    // checks a built-in instruction against the profile, explaining what isn't supported
+    pub fn check(&self, instruction: i64, position: usize) -> Result<(), String> {
+        let opcode = instruction % 100;
+        let name = match opcode_name(opcode) {
+            Some(name) => name,
+            None => return Ok(()),
+        };
+        if !self.allows_opcode(opcode) {
+            return Err(format!(
+                "Opcode {} ({}) at position {} is not part of the {} instruction set",
+                opcode, name, position, self
+            ));
+        }
+        for i in 1..=arity(opcode).unwrap() {
+            let mode = instruction / 10_i64.pow((i + 1).try_into().unwrap()) % 10;
+            if !self.allows_mode(mode) {
+                return Err(format!(
+                    "Parameter {} of instruction {} at position {} uses mode {}, which is not part of the {} instruction set",
+                    i, instruction, position, mode, self
+                ));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,379 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::rc::Rc;
+
+pub mod asynchronous;
+pub mod batch;
+pub mod compiler;
+pub mod coverage;
+pub mod device;
+pub mod diff;
+pub mod disasm;
+pub mod history;
+pub mod isa;
+pub mod opcode;
+pub mod optimize;
+pub mod patch;
+pub mod topology;
+pub mod visualize;
+
+use history::{History, StepRecord};
+use isa::IsaProfile;
+use opcode::{Call, Effect, OpcodeRegistry};
+
+// return codes of `proceed_until_halt`
+pub const HALTED: i64 = 0;
+pub const INVALID_OPCODE: i64 = 1;
+pub const AWAITING_INPUT: i64 = 2;
+pub const TRAPPED: i64 = 3;
+
+// notified of every memory access a machine makes
+pub trait Observer {
+    // an instruction of len words starting at position is about to execute
+    fn on_execute(&mut self, _position: usize, _len: usize) {}
+
+    fn on_read(&mut self, _address: usize) {}
+
+    fn on_write(&mut self, _address: usize, _value: i64) {}
+}
+
+// lets one observer be shared between machines, e.g. to aggregate several runs
+impl<O: Observer> Observer for Rc<RefCell<O>> {
+    fn on_execute(&mut self, position: usize, len: usize) {
+        self.borrow_mut().on_execute(position, len)
+    }
+
+    fn on_read(&mut self, address: usize) {
+        self.borrow_mut().on_read(address)
+    }
+
+    fn on_write(&mut self, address: usize, value: i64) {
+        self.borrow_mut().on_write(address, value)
+    }
+}
+
+pub struct IntCodeMachine {
+    pub instructions: Vec<i64>,
+    pub position: usize,
+    pub relative_base: i64,
+    pub extensions: OpcodeRegistry,
+    // set when an extension opcode halts the program with an explicit code
+    pub exit_code: Option<i64>,
+    pub profile: IsaProfile,
+    // explanation of the last INVALID_OPCODE stop
+    pub error: Option<String>,
+    pub history: Option<History>,
+    // inputs given back by stepping backwards, consumed before any new input
+    rewound_input: VecDeque<i64>,
+    recording: Option<StepRecord>,
+    observers: Vec<Box<dyn Observer>>,
+    len_instructions: usize,
+}
+
+impl IntCodeMachine {
+    pub fn new(instructions: Vec<i64>) -> IntCodeMachine {
+        let len_instructions = instructions.len();
+        IntCodeMachine {
+            instructions,
+            position: 0,
+            relative_base: 0,
+            extensions: OpcodeRegistry::new(),
+            exit_code: None,
+            profile: IsaProfile::default(),
+            error: None,
+            history: None,
+            rewound_input: VecDeque::new(),
+            recording: None,
+            observers: vec![],
+            len_instructions,
+        }
+    }
+
+    pub fn observe<O: Observer + 'static>(&mut self, observer: O) {
+        self.observers.push(Box::new(observer));
+    }
+
+    pub fn proceed_until_halt(&mut self, mut input: VecDeque<i64>) -> (VecDeque<i64>, i64) {
+        let mut output: VecDeque<i64> = VecDeque::new();
+        loop {
+            if let Some(code) = self.step(&mut input, &mut output) {
+                return (output, code);
+            }
+        }
+    }
+
+    // executes a single instruction, returning the code to stop with if it can't proceed
+    pub fn step(&mut self, input: &mut VecDeque<i64>, output: &mut VecDeque<i64>) -> Option<i64> {
+        if self.history.is_none() {
+            return self.execute(input, output);
+        }
+        self.recording = Some(StepRecord::start(self));
+        let code = self.execute(input, output);
+        let record = self.recording.take().unwrap();
+        if record.changed(self) {
+            self.history.as_mut().unwrap().push(record);
+        }
+        code
+    }
+
+    fn execute(&mut self, input: &mut VecDeque<i64>, output: &mut VecDeque<i64>) -> Option<i64> {
+        if self.exit_code.is_some() || self.position >= self.len_instructions {
+            return Some(HALTED);
+        }
+        if self.instructions[self.position] == 99 {
+            for observer in self.observers.iter_mut() {
+                observer.on_execute(self.position, 1);
+            }
+            return Some(HALTED);
+        }
+        let opcode_param: i64 = self.instructions[self.position];
+
+        let opcode_size: u32 = 2;
+        let opcode: i64 = opcode_param % 10_i64.pow(opcode_size);
+
+        if let Err(error) = self.profile.check(opcode_param, self.position) {
+            self.error = Some(error);
+            return Some(INVALID_OPCODE);
+        }
+
+        if opcode == 3 && input.is_empty() && self.rewound_input.is_empty() {
+            return Some(AWAITING_INPUT);
+        }
+
+        if !self.observers.is_empty() {
+            let arity = isa::arity(opcode).or(self.extensions.get(opcode).map(|e| e.arity));
+            if let Some(arity) = arity {
+                for observer in self.observers.iter_mut() {
+                    observer.on_execute(self.position, arity + 1);
+                }
+            }
+        }
+
+        let interval: usize;
+
+        match opcode {
+            1 => {
+                let param_values = self.get_param_values(3);
+                let target: usize = param_values[2].0;
+                self.write(target, param_values[0].1 + param_values[1].1);
+                interval = 4;
+            }
+            2 => {
+                let param_values = self.get_param_values(3);
+                let target: usize = param_values[2].0;
+                self.write(target, param_values[0].1 * param_values[1].1);
+                interval = 4;
+            }
+            3 => {
+                let param_values = self.get_param_values(1);
+                let target: usize = param_values[0].0;
+                let value = match self.rewound_input.pop_front() {
+                    Some(value) => value,
+                    None => input.pop_front().expect("input deque empty"),
+                };
+                if let Some(record) = self.recording.as_mut() {
+                    record.input = Some(value);
+                }
+                self.write(target, value);
+                interval = 2;
+            }
+            4 => {
+                let param_values = self.get_param_values(1);
+                if let Some(record) = self.recording.as_mut() {
+                    record.output = Some(param_values[0].1);
+                }
+                output.push_back(param_values[0].1);
+                interval = 2;
+            }
+            5 => {
+                let param_values = self.get_param_values(2);
+                if param_values[0].1 != 0 {
+                    self.position = param_values[1].1.try_into().unwrap();
+                    interval = 0;
+                } else {
+                    interval = 3;
+                }
+            }
+            6 => {
+                let param_values = self.get_param_values(2);
+                if param_values[0].1 == 0 {
+                    self.position = param_values[1].1.try_into().unwrap();
+                    interval = 0;
+                } else {
+                    interval = 3;
+                }
+            }
+            7 => {
+                let param_values = self.get_param_values(3);
+                let target: usize = param_values[2].0;
+                self.write(
+                    target,
+                    if param_values[0].1 < param_values[1].1 {
+                        1
+                    } else {
+                        0
+                    },
+                );
+                interval = 4;
+            }
+            8 => {
+                let param_values = self.get_param_values(3);
+                let target: usize = param_values[2].0;
+                self.write(
+                    target,
+                    if param_values[0].1 == param_values[1].1 {
+                        1
+                    } else {
+                        0
+                    },
+                );
+                interval = 4;
+            }
+            9 => {
+                let param_values = self.get_param_values(1);
+                let relative_base_change = param_values[0].1;
+                self.relative_base += relative_base_change;
+                interval = 2;
+            }
+
+            _ => return self.execute_extension(opcode, output),
+        }
+
+        self.position += interval;
+        None
+    }
+
+    fn execute_extension(&mut self, opcode: i64, output: &mut VecDeque<i64>) -> Option<i64> {
+        let (arity, write_targets) = match self.extensions.get(opcode) {
+            Some(extension) => (extension.arity, extension.write_targets.clone()),
+            None => {
+                self.error = Some(format!(
+                    "Invalid opcode: {} at position {}",
+                    opcode, self.position
+                ));
+                return Some(INVALID_OPCODE);
+            }
+        };
+        let param_values = self.get_param_values(arity);
+        let targets: Vec<usize> = write_targets.iter().map(|t| param_values[*t].0).collect();
+        if targets.contains(&usize::MAX) {
+            self.error = Some(format!(
+                "Opcode {} at position {} writes to an immediate mode parameter",
+                opcode, self.position
+            ));
+            return Some(INVALID_OPCODE);
+        }
+
+        let mut call = Call {
+            opcode,
+            position: self.position,
+            relative_base: self.relative_base,
+            params: param_values.iter().map(|p| p.1).collect(),
+            writes: targets.iter().map(|t| self.instructions[*t]).collect(),
+            output,
+        };
+        let handler = &mut self.extensions.get_mut(opcode).unwrap().handler;
+        let effect = handler(&mut call);
+        for (target, value) in targets.iter().zip(call.writes) {
+            self.write(*target, value);
+        }
+
+        match effect {
+            Effect::Continue => {
+                self.position += arity + 1;
+                None
+            }
+            Effect::Jump(position) => {
+                self.position = position;
+                None
+            }
+            Effect::Halt(exit_code) => {
+                self.exit_code = Some(exit_code);
+                Some(HALTED)
+            }
+            Effect::Trap => {
+                self.position += arity + 1;
+                Some(TRAPPED)
+            }
+        }
+    }
+
+    fn write(&mut self, address: usize, value: i64) {
+        if let Some(record) = self.recording.as_mut() {
+            record.writes.push((address, self.instructions[address]));
+        }
+        for observer in self.observers.iter_mut() {
+            observer.on_write(address, value);
+        }
+        self.instructions[address] = value;
+    }
+
+    fn extend_instructions(&mut self, length: usize) {
+        for _ in self.instructions.len()..=length {
+            self.instructions.push(0);
+        }
+    }
+
+    fn get_param_values(&mut self, num_parameters: usize) -> Vec<(usize, i64)> {
+        let mut param_values: Vec<(usize, i64)> = vec![];
+        for i in 1..=num_parameters {
+            let parameter_mode =
+                self.instructions[self.position] / 10_i64.pow((i + 1).try_into().unwrap()) % 10;
+            if parameter_mode == 0 {
+                // position mode
+                let position: usize = self.instructions[self.position + i].try_into().unwrap();
+                self.extend_instructions(position);
+                param_values.push((position, self.instructions[position]))
+            } else if parameter_mode == 1 {
+                // immediate mode
+                param_values.push((usize::MAX, self.instructions[self.position + i]))
+            } else {
+                // relative mode
+                let offset = self.instructions[self.position + i];
+                let position: usize = (self.relative_base + offset).try_into().unwrap();
+                self.extend_instructions(position);
+                param_values.push((position, self.instructions[position]))
+            }
+        }
+        if !self.observers.is_empty() {
+            self.notify_reads(&param_values);
+        }
+        param_values
+    }
+
+    fn notify_reads(&mut self, param_values: &[(usize, i64)]) {
+        let opcode = self.instructions[self.position] % 100;
+        let write_targets = match self.extensions.get(opcode) {
+            Some(extension) => extension.write_targets.clone(),
+            None => isa::write_targets(opcode).to_vec(),
+        };
+        for (i, (address, _)) in param_values.iter().enumerate() {
+            if *address != usize::MAX && !write_targets.contains(&i) {
+                for observer in self.observers.iter_mut() {
+                    observer.on_read(*address);
+                }
+            }
+        }
+    }
+}
+
+pub fn parse_program(source: &str) -> Result<Vec<i64>> {
+    source
+        .trim()
+        .split(',')
+        .map(|v| {
+            v.trim().parse().map_err(|_| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Invalid intcode value: {:?}", v),
+                )
+            })
+        })
+        .collect()
+}
+
+pub fn parse(path: &str) -> Result<Vec<i64>> {
+    parse_program(&fs::read_to_string(path)?)
+}
@@ -0,0 +1,192 @@
+// Runs any intcode program:
+//
+//   intcode PROGRAM [--input 1,2,3] [--input-file PATH] [--stdin] [--ascii] [--text]
+//                   [--set ADDRESS=VALUE]... [--patch SPEC]... [--max-steps N] [--dump]
+//
+// a patch SPEC is either inline, 1=12,2=2, or FILE:NAME for a named patch in a patch file;
+// inputs are numbers separated by commas or whitespace, or with --ascii text fed one
+// character code at a time; --text prints outputs below 128 as characters
+
+use intcode::patch::PatchSpec;
+use intcode::{IntCodeMachine, AWAITING_INPUT, HALTED};
+use std::collections::VecDeque;
+use std::env;
+use std::fs;
+use std::io::{self, Error, ErrorKind, Read, Result, Write};
+use std::path::Path;
+use std::process;
+
+const USAGE: &str = "usage: intcode PROGRAM [--input VALUES] [--input-file PATH] [--stdin] \
+[--ascii] [--text] [--set ADDRESS=VALUE]... [--patch SPEC]... [--max-steps N] [--dump]";
+
+#[derive(Default)]
+struct Options {
+    program: String,
+    // raw input text in the order given, interpreted once --ascii is known
+    inputs: Vec<String>,
+    ascii: bool,
+    text: bool,
+    patch: PatchSpec,
+    max_steps: Option<u64>,
+    dump: bool,
+}
+
+fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidInput, message)
+}
+
+fn parse_args(args: &[String]) -> Result<Options> {
+    let mut options = Options::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .cloned()
+                .ok_or_else(|| invalid(format!("{} needs a value", name)))
+        };
+        match arg.as_str() {
+            "--input" => options.inputs.push(value(arg)?),
+            "--input-file" => options.inputs.push(fs::read_to_string(value(arg)?)?),
+            "--stdin" => {
+                let mut input = String::new();
+                io::stdin().read_to_string(&mut input)?;
+                options.inputs.push(input);
+            }
+            "--ascii" => options.ascii = true,
+            "--text" => options.text = true,
+            "--set" => {
+                let patch = PatchSpec::parse(&value(arg)?)?;
+                options.patch.writes.extend(patch.writes);
+            }
+            "--patch" => {
+                let patch = load_patch(&value(arg)?)?;
+                options.patch.writes.extend(patch.writes);
+            }
+            "--max-steps" => {
+                let steps = value(arg)?;
+                options.max_steps = Some(
+                    steps
+                        .parse()
+                        .map_err(|_| invalid(format!("invalid step count {}", steps)))?,
+                );
+            }
+            "--dump" => options.dump = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            _ if arg.starts_with("--") => return Err(invalid(format!("unknown flag {}", arg))),
+            _ if options.program.is_empty() => options.program = arg.clone(),
+            _ => return Err(invalid(format!("unexpected argument {}", arg))),
+        }
+    }
+    if options.program.is_empty() {
+        return Err(invalid(USAGE.to_string()));
+    }
+    Ok(options)
+}
+
+fn load_patch(spec: &str) -> Result<PatchSpec> {
+    if spec.contains('=') {
+        return PatchSpec::parse(spec);
+    }
+    let (path, name) = spec
+        .rsplit_once(':')
+        .ok_or_else(|| invalid(format!("expected FILE:NAME or inline patch, got {}", spec)))?;
+    PatchSpec::load(Path::new(path))?
+        .remove(name)
+        .ok_or_else(|| invalid(format!("no patch named {} in {}", name, path)))
+}
+
+fn parse_input(options: &Options) -> Result<VecDeque<i64>> {
+    let mut input = VecDeque::new();
+    for text in options.inputs.iter() {
+        if options.ascii {
+            input.extend(text.chars().map(|c| c as i64));
+            continue;
+        }
+        for value in text.split(|c: char| c == ',' || c.is_whitespace()) {
+            if value.is_empty() {
+                continue;
+            }
+            input.push_back(
+                value
+                    .parse()
+                    .map_err(|_| invalid(format!("invalid input value {:?}", value)))?,
+            );
+        }
+    }
+    Ok(input)
+}
+
+fn print_output(output: &mut VecDeque<i64>, text: bool) -> Result<()> {
+    let mut stdout = io::stdout().lock();
+    for value in output.drain(..) {
+        match char::from_u32(value as u32) {
+            Some(c) if text && (0..128).contains(&value) => write!(stdout, "{}", c)?,
+            _ => writeln!(stdout, "{}", value)?,
+        }
+    }
+    stdout.flush()
+}
+
+fn run(options: &Options) -> Result<i32> {
+    let program = intcode::parse(&options.program)?;
+    let mut machine = IntCodeMachine::new(program);
+    machine.apply_patch(&options.patch)?;
+
+    let mut input = parse_input(options)?;
+    let mut output = VecDeque::new();
+    let mut steps = 0;
+    let code = loop {
+        if options.max_steps == Some(steps) {
+            break None;
+        }
+        steps += 1;
+        if let Some(code) = machine.step(&mut input, &mut output) {
+            break Some(code);
+        }
+        // flushed as it goes so long running programs show progress
+        if output.len() >= 1024 {
+            print_output(&mut output, options.text)?;
+        }
+    };
+    print_output(&mut output, options.text)?;
+
+    if options.dump {
+        let memory: Vec<String> = machine.instructions.iter().map(|v| v.to_string()).collect();
+        println!("{}", memory.join(","));
+    }
+    let exit_code = match code {
+        Some(HALTED) => 0,
+        Some(AWAITING_INPUT) => {
+            eprintln!(
+                "program is waiting for input at position {}",
+                machine.position
+            );
+            2
+        }
+        Some(_) => {
+            eprintln!(
+                "program stopped at position {}: {}",
+                machine.position,
+                machine.error.unwrap_or_default()
+            );
+            1
+        }
+        None => {
+            eprintln!(
+                "stopped after {} steps at position {}",
+                steps, machine.position
+            );
+            3
+        }
+    };
+    Ok(exit_code)
+}
+
+fn main() -> Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = parse_args(&args)?;
+    process::exit(run(&options)?);
+}
@@ -0,0 +1,118 @@
+use std::collections::{HashMap, VecDeque};
+use std::io::{Error, ErrorKind, Result};
+
+// opcodes with built-in meaning, which can't be overridden
+const BUILT_IN: [i64; 10] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 99];
+
+pub enum Effect {
+    // move on to the next instruction
+    Continue,
+    Jump(usize),
+    // stop for good, recording the exit code on the machine
+    Halt(i64),
+    // stop after this instruction, the machine can be resumed afterwards
+    Trap,
+}
+
+pub struct Call<'a> {
+    pub opcode: i64,
+    pub position: usize,
+    pub relative_base: i64,
+    // resolved value of every parameter, write targets included
+    pub params: Vec<i64>,
+    // one value per write target, written back to memory once the handler returns
+    pub writes: Vec<i64>,
+    pub output: &'a mut VecDeque<i64>,
+}
+
+pub struct Extension {
+    pub arity: usize,
+    // indexes of the parameters that are written to
+    pub write_targets: Vec<usize>,
+    pub handler: Box<dyn FnMut(&mut Call) -> Effect>,
+}
+
+#[derive(Default)]
+pub struct OpcodeRegistry {
+    extensions: HashMap<i64, Extension>,
+}
+
+impl OpcodeRegistry {
+    pub fn new() -> OpcodeRegistry {
+        OpcodeRegistry::default()
+    }
+
+    pub fn register<F>(
+        &mut self,
+        opcode: i64,
+        arity: usize,
+        write_targets: &[usize],
+        handler: F,
+    ) -> Result<()>
+    where
+        F: FnMut(&mut Call) -> Effect + 'static,
+    {
+        if !(1..=98).contains(&opcode) || BUILT_IN.contains(&opcode) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Opcode {} can't be registered", opcode),
+            ));
+        }
+        if let Some(target) = write_targets.iter().find(|t| **t >= arity) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Write target {} out of range for opcode {} with {} parameters",
+                    target, opcode, arity
+                ),
+            ));
+        }
+        self.extensions.insert(
+            opcode,
+            Extension {
+                arity,
+                write_targets: write_targets.to_vec(),
+                handler: Box::new(handler),
+            },
+        );
+        Ok(())
+    }
+
+    pub fn get(&self, opcode: i64) -> Option<&Extension> {
+        self.extensions.get(&opcode)
+    }
+
+    pub fn get_mut(&mut self, opcode: i64) -> Option<&mut Extension> {
+        self.extensions.get_mut(&opcode)
+    }
+
+    // 10: debug-print a value to stderr
+    // 11: trap back to the caller
+    // 12: halt with the given exit code
+    // 13: write a pseudo-random number, seeded with `seed`
+    pub fn with_standard_extensions(seed: u64) -> OpcodeRegistry {
+        let mut registry = OpcodeRegistry::new();
+        registry
+            .register(10, 1, &[], |call| {
+                eprintln!("[{}] {}", call.position, call.params[0]);
+                Effect::Continue
+            })
+            .unwrap();
+        registry.register(11, 0, &[], |_| Effect::Trap).unwrap();
+        registry
+            .register(12, 1, &[], |call| Effect::Halt(call.params[0]))
+            .unwrap();
+        // xorshift, the state must never be zero
+        let mut state = seed.max(1);
+        registry
+            .register(13, 1, &[0], move |call| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                call.writes[0] = (state >> 1) as i64;
+                Effect::Continue
+            })
+            .unwrap();
+        registry
+    }
+}
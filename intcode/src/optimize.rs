@@ -0,0 +1,269 @@
+// Peephole optimizer. Only instructions that were actually executed while running the program
+// on a corpus of inputs are touched, since anything else may be data, and instructions that
+// overlap an address the program writes to or reads as data are left alone. Rewrites happen
+// in place; dead jumps are then cut out by shifting the code after them down, which is only
+// kept if the result still behaves the same on the corpus, as pointers computed at runtime
+// can't be relocated.
+
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use std::rc::Rc;
+
+use crate::coverage::Coverage;
+use crate::disasm::{self, Instruction};
+use crate::IntCodeMachine;
+
+// upper bound on instructions per corpus run, so programs that never finish are caught
+const MAX_STEPS: u64 = 10_000_000;
+
+#[derive(Debug, Default)]
+pub struct Optimized {
+    pub program: Vec<i64>,
+    pub folded: usize,
+    pub jumps_simplified: usize,
+    pub dead_jumps_removed: usize,
+}
+
+// outputs and stop code of running the machine on input, None if it didn't stop in time
+fn run(mut machine: IntCodeMachine, input: &[i64]) -> Option<(Vec<i64>, i64)> {
+    let mut input = VecDeque::from(input.to_vec());
+    let mut output = VecDeque::new();
+    for _ in 0..MAX_STEPS {
+        if let Some(code) = machine.step(&mut input, &mut output) {
+            return Some((output.into(), code));
+        }
+    }
+    None
+}
+
+// checks both programs produce the same outputs and stop the same way on every input
+pub fn equivalent(original: &[i64], optimized: &[i64], corpus: &[Vec<i64>]) -> Result<(), String> {
+    for input in corpus {
+        let expected = run(IntCodeMachine::new(original.to_vec()), input);
+        if expected.is_none() {
+            return Err(format!(
+                "Original program didn't finish on input {:?}",
+                input
+            ));
+        }
+        let actual = run(IntCodeMachine::new(optimized.to_vec()), input);
+        if actual != expected {
+            return Err(format!(
+                "Programs differ on input {:?}: {:?} vs {:?}",
+                input, expected, actual
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn encode(opcode: i64, params: &[(i64, i64)]) -> Vec<i64> {
+    let mut instruction = opcode;
+    for (i, (mode, _)) in params.iter().enumerate() {
+        instruction += mode * 10_i64.pow((i + 2).try_into().unwrap());
+    }
+    let mut words = vec![instruction];
+    words.extend(params.iter().map(|(_, value)| value));
+    words
+}
+
+fn rewrite(program: &mut [i64], instruction: &Instruction, words: Vec<i64>) -> bool {
+    let span = instruction.position..instruction.position + words.len();
+    if program[span.clone()] == words[..] {
+        return false;
+    }
+    program[span].copy_from_slice(&words);
+    true
+}
+
+// fails if the program doesn't finish on some input of the corpus
+pub fn optimize(program: &[i64], corpus: &[Vec<i64>]) -> Result<Optimized, String> {
+    let coverage = Rc::new(RefCell::new(Coverage::new(program)));
+    for input in corpus {
+        let mut machine = IntCodeMachine::new(program.to_vec());
+        machine.observe(coverage.clone());
+        if run(machine, input).is_none() {
+            return Err(format!(
+                "Original program didn't finish on input {:?}",
+                input
+            ));
+        }
+    }
+    let coverage = coverage.borrow();
+
+    let mut protected: HashSet<usize> = (0..program.len())
+        .filter(|a| coverage.writes[*a] > 0 || coverage.reads[*a] > 0)
+        .collect();
+    let instructions: Vec<Instruction> = (0..program.len())
+        .filter(|a| coverage.executed[*a] > 0)
+        .filter_map(|a| disasm::decode(program, a))
+        .collect();
+    for instruction in instructions.iter() {
+        if matches!(instruction.opcode, 1 | 2 | 3 | 7 | 8) {
+            let (mode, target) = *instruction.params.last().unwrap();
+            if mode == 0 {
+                protected.insert(target as usize);
+            }
+        }
+    }
+    let instructions: Vec<Instruction> = instructions
+        .into_iter()
+        .filter(|i| (i.position..i.position + i.size()).all(|a| !protected.contains(&a)))
+        .collect();
+
+    let mut optimized = Optimized {
+        program: program.to_vec(),
+        ..Optimized::default()
+    };
+    let mut dead_jumps = BTreeSet::new();
+    for instruction in instructions.iter() {
+        let params = &instruction.params;
+        match instruction.opcode {
+            1 | 2 | 7 | 8 if params[0].0 == 1 && params[1].0 == 1 => {
+                let (a, b) = (params[0].1, params[1].1);
+                let result = match instruction.opcode {
+                    1 => a.checked_add(b),
+                    2 => a.checked_mul(b),
+                    7 => Some((a < b) as i64),
+                    _ => Some((a == b) as i64),
+                };
+                if let Some(result) = result {
+                    let words = encode(1, &[(1, result), (1, 0), params[2]]);
+                    if rewrite(&mut optimized.program, instruction, words) {
+                        optimized.folded += 1;
+                    }
+                }
+            }
+            5 | 6 => {
+                let next = (instruction.position + instruction.size()) as i64;
+                let (condition_mode, condition) = params[0];
+                let taken =
+                    (condition_mode == 1).then_some((condition != 0) == (instruction.opcode == 5));
+                if taken == Some(false) || params[1] == (1, next) {
+                    dead_jumps.insert(instruction.position);
+                } else if taken == Some(true) {
+                    let words = encode(5, &[(1, 1), params[1]]);
+                    if rewrite(&mut optimized.program, instruction, words) {
+                        optimized.jumps_simplified += 1;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if equivalent(program, &optimized.program, corpus).is_err() {
+        return Ok(Optimized {
+            program: program.to_vec(),
+            ..Optimized::default()
+        });
+    }
+
+    if !dead_jumps.is_empty() {
+        let compacted = remove_jumps(&optimized.program, &instructions, &dead_jumps);
+        if equivalent(program, &compacted, corpus).is_ok() {
+            optimized.program = compacted;
+            optimized.dead_jumps_removed = dead_jumps.len();
+        }
+    }
+    Ok(optimized)
+}
+
+// drops the three words of each dead jump and relocates the addresses other instructions use
+fn remove_jumps(
+    program: &[i64],
+    instructions: &[Instruction],
+    dead_jumps: &BTreeSet<usize>,
+) -> Vec<i64> {
+    let removed = |address: usize| {
+        dead_jumps
+            .iter()
+            .filter(|jump| **jump + 3 <= address)
+            .count()
+            * 3
+    };
+    // addresses inside a removed jump land on whatever followed it, which is equivalent
+    let relocate = |address: i64| -> i64 {
+        if address < 0 || address as usize >= program.len() {
+            return address;
+        }
+        let address = address as usize;
+        let address = match dead_jumps
+            .iter()
+            .find(|j| (**j..**j + 3).contains(&address))
+        {
+            Some(jump) => jump + 3,
+            None => address,
+        };
+        (address - removed(address)) as i64
+    };
+
+    let mut relocated = program.to_vec();
+    for instruction in instructions.iter() {
+        if dead_jumps.contains(&instruction.position) {
+            continue;
+        }
+        let is_jump = matches!(instruction.opcode, 5 | 6);
+        for (i, (mode, value)) in instruction.params.iter().enumerate() {
+            if *mode == 0 || (is_jump && i == 1 && *mode == 1) {
+                relocated[instruction.position + i + 1] = relocate(*value);
+            }
+        }
+    }
+    relocated
+        .into_iter()
+        .enumerate()
+        .filter(|(a, _)| !dead_jumps.iter().any(|j| (*j..*j + 3).contains(a)))
+        .map(|(_, value)| value)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{equivalent, optimize};
+
+    #[test]
+    fn folds_constant_arithmetic() {
+        // prints 2 + 3 and 4 * 5
+        let program = vec![1101, 2, 3, 13, 1102, 4, 5, 14, 4, 13, 4, 14, 99, 0, 0];
+        let corpus = vec![vec![]];
+        let optimized = optimize(&program, &corpus).unwrap();
+        assert_eq!(optimized.folded, 2);
+        assert_eq!(&optimized.program[..8], &[1101, 5, 0, 13, 1101, 20, 0, 14]);
+        assert!(equivalent(&program, &optimized.program, &corpus).is_ok());
+    }
+
+    #[test]
+    fn simplifies_always_taken_jumps() {
+        // jump-if-false on an immediate 0 always jumps
+        let program = vec![1106, 0, 4, 99, 104, 7, 99];
+        let optimized = optimize(&program, &[vec![]]).unwrap();
+        assert_eq!(optimized.jumps_simplified, 1);
+        assert_eq!(optimized.program, vec![1105, 1, 4, 99, 104, 7, 99]);
+    }
+
+    #[test]
+    fn removes_dead_jumps() {
+        // the first jump is never taken, the second one's target moves down with the code
+        let program = vec![1105, 0, 0, 1105, 1, 7, 99, 104, 7, 99];
+        let optimized = optimize(&program, &[vec![]]).unwrap();
+        assert_eq!(optimized.dead_jumps_removed, 1);
+        assert_eq!(optimized.program, vec![1105, 1, 4, 99, 104, 7, 99]);
+    }
+
+    #[test]
+    fn keeps_code_read_as_data() {
+        // the first instruction's 2 is also printed, so only the multiplication is folded
+        let program = vec![1101, 2, 3, 13, 1102, 4, 5, 14, 4, 1, 4, 14, 99, 0, 0];
+        let corpus = vec![vec![]];
+        let optimized = optimize(&program, &corpus).unwrap();
+        assert_eq!(optimized.folded, 1);
+        assert_eq!(&optimized.program[..8], &[1101, 2, 3, 13, 1101, 20, 0, 14]);
+        assert!(equivalent(&program, &optimized.program, &corpus).is_ok());
+    }
+
+    #[test]
+    fn rejects_programs_that_never_finish() {
+        assert!(optimize(&[1105, 1, 0], &[vec![]]).is_err());
+    }
+}
@@ -0,0 +1,137 @@
+// Memory patches, written as address=value pairs separated by commas or whitespace:
+//
+//   1=12, 2=2
+//
+// Patch files hold one named patch per line, with # starting a comment:
+//
+//   # day 02
+//   gravity-assist: 1=12 2=2
+//   free-play: 0=2
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+
+use crate::history::StepRecord;
+use crate::IntCodeMachine;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PatchSpec {
+    // (address, value) pairs, applied in order
+    pub writes: Vec<(usize, i64)>,
+}
+
+fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+impl PatchSpec {
+    pub fn new() -> PatchSpec {
+        PatchSpec::default()
+    }
+
+    pub fn set(&mut self, address: usize, value: i64) -> &mut PatchSpec {
+        self.writes.push((address, value));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.writes.is_empty()
+    }
+
+    pub fn parse(source: &str) -> Result<PatchSpec> {
+        let mut spec = PatchSpec::new();
+        for pair in source.split(|c: char| c == ',' || c.is_whitespace()) {
+            if pair.is_empty() {
+                continue;
+            }
+            let (address, value) = pair
+                .split_once('=')
+                .ok_or_else(|| invalid(format!("expected address=value, got {:?}", pair)))?;
+            let address = address
+                .parse()
+                .map_err(|_| invalid(format!("invalid patch address {:?}", address)))?;
+            let value = value
+                .parse()
+                .map_err(|_| invalid(format!("invalid patch value {:?}", value)))?;
+            spec.set(address, value);
+        }
+        Ok(spec)
+    }
+
+    // named patches, one `name: address=value ...` per line
+    pub fn parse_named(source: &str) -> Result<BTreeMap<String, PatchSpec>> {
+        let mut specs = BTreeMap::new();
+        for (i, line) in source.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let in_line = |e: Error| invalid(format!("line {}: {}", i + 1, e));
+            let (name, spec) = line
+                .split_once(':')
+                .ok_or_else(|| in_line(invalid("expected name: patches".to_string())))?;
+            let name = name.trim().to_string();
+            let spec = PatchSpec::parse(spec).map_err(in_line)?;
+            if specs.insert(name.clone(), spec).is_some() {
+                return Err(in_line(invalid(format!("duplicate patch {}", name))));
+            }
+        }
+        Ok(specs)
+    }
+
+    pub fn load(path: &Path) -> Result<BTreeMap<String, PatchSpec>> {
+        PatchSpec::parse_named(&fs::read_to_string(path)?)
+    }
+
+    // every address has to be inside the program as loaded, not memory grown while running
+    pub fn validate(&self, program_len: usize) -> Result<()> {
+        match self
+            .writes
+            .iter()
+            .find(|(address, _)| *address >= program_len)
+        {
+            Some((address, _)) => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Patch address {} is outside the program of length {}",
+                    address, program_len
+                ),
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for PatchSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let pairs: Vec<String> = self
+            .writes
+            .iter()
+            .map(|(address, value)| format!("{}={}", address, value))
+            .collect();
+        write!(f, "{}", pairs.join(","))
+    }
+}
+
+impl IntCodeMachine {
+    // writes the patch into memory, before the run or between steps; with history enabled the
+    // patch is recorded as a step of its own, so it shows up in the trace and can be undone
+    pub fn apply_patch(&mut self, spec: &PatchSpec) -> Result<()> {
+        spec.validate(self.len_instructions)?;
+        if self.history.is_some() {
+            let mut record = StepRecord::start(self);
+            record.patched = true;
+            self.recording = Some(record);
+        }
+        for (address, value) in spec.writes.iter() {
+            self.write(*address, *value);
+        }
+        if let Some(record) = self.recording.take() {
+            self.history.as_mut().unwrap().push(record);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,345 @@
+// Networks of intcode machines described in a small text format, one directive per line:
+//
+//   # day 07 feedback loop
+//   machine a program=input.txt input=5,0
+//   machine b program=input.txt input=6
+//   route a -> b          # chain, every output of a is queued as input of b
+//   route b -> a          # routes may form rings
+//   route a -> b, c       # fan-out, each listed machine gets a copy
+//   route a -> *          # broadcast to every other machine
+//   route a -> packets    # (address, x, y) triples, x and y go to the machine with that address
+//
+// machines also take `address=N` (defaults to their index) and `idle=N`, a value fed
+// whenever they ask for input while their queue is empty, e.g. -1 for network cards
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::mem;
+use std::path::Path;
+
+use crate::{IntCodeMachine, AWAITING_INPUT, HALTED};
+
+pub struct MachineSpec {
+    pub name: String,
+    pub program: Vec<i64>,
+    pub input: Vec<i64>,
+    pub address: i64,
+    pub idle: Option<i64>,
+}
+
+pub enum Target {
+    Machines(Vec<usize>),
+    Broadcast,
+    Packets,
+}
+
+pub struct Route {
+    pub from: usize,
+    pub to: Target,
+}
+
+#[derive(Default)]
+pub struct Topology {
+    pub machines: Vec<MachineSpec>,
+    pub routes: Vec<Route>,
+}
+
+fn invalid(line_number: usize, message: String) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("line {}: {}", line_number, message),
+    )
+}
+
+fn parse_values(line_number: usize, values: &str) -> Result<Vec<i64>> {
+    values
+        .split(',')
+        .filter(|v| !v.trim().is_empty())
+        .map(|v| parse_value(line_number, v))
+        .collect()
+}
+
+fn parse_value(line_number: usize, value: &str) -> Result<i64> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| invalid(line_number, format!("invalid value {:?}", value)))
+}
+
+impl Topology {
+    pub fn load(path: &Path) -> Result<Topology> {
+        let base = path.parent().unwrap_or(Path::new("."));
+        Topology::parse(&fs::read_to_string(path)?, base)
+    }
+
+    // program paths are relative to base
+    pub fn parse(source: &str, base: &Path) -> Result<Topology> {
+        let mut topology = Topology::default();
+        let mut programs: HashMap<String, Vec<i64>> = HashMap::new();
+        for (i, line) in source.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line.split('#').next().unwrap().trim();
+            let mut words = line.split_whitespace();
+            match words.next() {
+                None => continue,
+                Some("machine") => {
+                    let name = words
+                        .next()
+                        .ok_or_else(|| invalid(line_number, "machine needs a name".to_string()))?;
+                    if topology.index(name).is_some() {
+                        return Err(invalid(line_number, format!("duplicate machine {}", name)));
+                    }
+                    let mut spec = MachineSpec {
+                        name: name.to_string(),
+                        program: vec![],
+                        input: vec![],
+                        address: topology.machines.len() as i64,
+                        idle: None,
+                    };
+                    let mut has_program = false;
+                    for attribute in words {
+                        let (key, value) = attribute.split_once('=').ok_or_else(|| {
+                            invalid(
+                                line_number,
+                                format!("expected key=value, got {}", attribute),
+                            )
+                        })?;
+                        match key {
+                            "program" => {
+                                if !programs.contains_key(value) {
+                                    let program = crate::parse_program(&fs::read_to_string(
+                                        base.join(value),
+                                    )?)?;
+                                    programs.insert(value.to_string(), program);
+                                }
+                                spec.program = programs[value].clone();
+                                has_program = true;
+                            }
+                            "input" => spec.input = parse_values(line_number, value)?,
+                            "address" => spec.address = parse_value(line_number, value)?,
+                            "idle" => spec.idle = Some(parse_value(line_number, value)?),
+                            _ => {
+                                return Err(invalid(
+                                    line_number,
+                                    format!("unknown machine attribute {}", key),
+                                ))
+                            }
+                        }
+                    }
+                    if !has_program {
+                        return Err(invalid(
+                            line_number,
+                            format!("machine {} has no program", name),
+                        ));
+                    }
+                    topology.machines.push(spec);
+                }
+                Some("route") => {
+                    let rest: Vec<&str> = words.collect();
+                    let rest = rest.join(" ");
+                    let (from, to) = rest
+                        .split_once("->")
+                        .ok_or_else(|| invalid(line_number, "expected route a -> b".to_string()))?;
+                    let from = topology.lookup(line_number, from.trim())?;
+                    let to = match to.trim() {
+                        "*" => Target::Broadcast,
+                        "packets" => Target::Packets,
+                        names => Target::Machines(
+                            names
+                                .split(',')
+                                .map(|name| topology.lookup(line_number, name.trim()))
+                                .collect::<Result<Vec<usize>>>()?,
+                        ),
+                    };
+                    topology.routes.push(Route { from, to });
+                }
+                Some(directive) => {
+                    return Err(invalid(
+                        line_number,
+                        format!("unknown directive {}", directive),
+                    ))
+                }
+            }
+        }
+        Ok(topology)
+    }
+
+    fn index(&self, name: &str) -> Option<usize> {
+        self.machines.iter().position(|m| m.name == name)
+    }
+
+    fn lookup(&self, line_number: usize, name: &str) -> Result<usize> {
+        self.index(name)
+            .ok_or_else(|| invalid(line_number, format!("unknown machine {}", name)))
+    }
+
+    // runs every machine in turn until all have halted, none can make progress or
+    // max_rounds is reached
+    pub fn run(&self, max_rounds: usize) -> Result<Report> {
+        let mut machines: Vec<IntCodeMachine> = self
+            .machines
+            .iter()
+            .map(|spec| IntCodeMachine::new(spec.program.clone()))
+            .collect();
+        let mut queues: Vec<VecDeque<i64>> = self
+            .machines
+            .iter()
+            .map(|spec| VecDeque::from(spec.input.clone()))
+            .collect();
+        let mut codes: Vec<Option<i64>> = vec![None; machines.len()];
+        let mut outputs: Vec<Vec<i64>> = vec![vec![]; machines.len()];
+        let mut packet_buffers: Vec<Vec<i64>> = vec![vec![]; machines.len()];
+        let mut unrouted = vec![];
+
+        let mut rounds = 0;
+        while rounds < max_rounds {
+            rounds += 1;
+            let mut progress = false;
+            for i in 0..machines.len() {
+                if codes[i] == Some(HALTED) {
+                    continue;
+                }
+                let mut input = mem::take(&mut queues[i]);
+                let fed = !input.is_empty();
+                if !fed && codes[i] == Some(AWAITING_INPUT) {
+                    match self.machines[i].idle {
+                        Some(idle) => input.push_back(idle),
+                        None => continue,
+                    }
+                }
+                let mut output = VecDeque::new();
+                let code = loop {
+                    if let Some(code) = machines[i].step(&mut input, &mut output) {
+                        break code;
+                    }
+                };
+                // whatever a halted machine didn't read stays pending
+                queues[i] = input;
+                if code != HALTED && code != AWAITING_INPUT {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!(
+                            "machine {}: {}",
+                            self.machines[i].name,
+                            machines[i].error.clone().unwrap_or_default()
+                        ),
+                    ));
+                }
+                progress |= fed || !output.is_empty() || codes[i] != Some(code);
+                codes[i] = Some(code);
+                for value in output {
+                    outputs[i].push(value);
+                    self.route(i, value, &mut queues, &mut packet_buffers[i], &mut unrouted);
+                }
+            }
+            if !progress {
+                break;
+            }
+        }
+
+        Ok(Report {
+            machines: self
+                .machines
+                .iter()
+                .zip(machines.iter())
+                .enumerate()
+                .map(|(i, (spec, machine))| MachineReport {
+                    name: spec.name.clone(),
+                    code: codes[i],
+                    position: machine.position,
+                    outputs: outputs[i].clone(),
+                    pending_input: queues[i].iter().copied().collect(),
+                })
+                .collect(),
+            unrouted,
+            rounds,
+        })
+    }
+
+    fn route(
+        &self,
+        from: usize,
+        value: i64,
+        queues: &mut [VecDeque<i64>],
+        packet_buffer: &mut Vec<i64>,
+        unrouted: &mut Vec<(i64, i64, i64)>,
+    ) {
+        for route in self.routes.iter().filter(|r| r.from == from) {
+            match &route.to {
+                Target::Machines(targets) => {
+                    for target in targets {
+                        queues[*target].push_back(value);
+                    }
+                }
+                Target::Broadcast => {
+                    for (i, queue) in queues.iter_mut().enumerate() {
+                        if i != from {
+                            queue.push_back(value);
+                        }
+                    }
+                }
+                Target::Packets => {
+                    packet_buffer.push(value);
+                    if packet_buffer.len() == 3 {
+                        let (address, x, y) =
+                            (packet_buffer[0], packet_buffer[1], packet_buffer[2]);
+                        packet_buffer.clear();
+                        match self.machines.iter().position(|m| m.address == address) {
+                            Some(target) => queues[target].extend([x, y]),
+                            None => unrouted.push((address, x, y)),
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub struct MachineReport {
+    pub name: String,
+    // None if the machine never ran
+    pub code: Option<i64>,
+    pub position: usize,
+    pub outputs: Vec<i64>,
+    pub pending_input: Vec<i64>,
+}
+
+pub struct Report {
+    pub machines: Vec<MachineReport>,
+    // packets addressed to no machine in the topology
+    pub unrouted: Vec<(i64, i64, i64)>,
+    pub rounds: usize,
+}
+
+impl Report {
+    pub fn machine(&self, name: &str) -> Option<&MachineReport> {
+        self.machines.iter().find(|m| m.name == name)
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} rounds", self.rounds)?;
+        for machine in self.machines.iter() {
+            let status = match machine.code {
+                Some(HALTED) => "halted",
+                Some(AWAITING_INPUT) => "awaiting input",
+                _ => "not started",
+            };
+            writeln!(
+                f,
+                "{}: {} at position {}, outputs {:?}",
+                machine.name, status, machine.position, machine.outputs
+            )?;
+            if !machine.pending_input.is_empty() {
+                writeln!(f, "  pending input {:?}", machine.pending_input)?;
+            }
+        }
+        for packet in self.unrouted.iter() {
+            writeln!(f, "unrouted packet {:?}", packet)?;
+        }
+        Ok(())
+    }
+}
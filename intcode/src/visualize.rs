@@ -0,0 +1,173 @@
+use std::fs;
+use std::io::Result;
+use std::path::Path;
+
+use crate::coverage::Coverage;
+use crate::Observer;
+
+// how addresses are laid out in an image, row by row, each as a square cell
+#[derive(Clone, Copy)]
+pub struct Layout {
+    pub columns: usize,
+    pub cell_size: usize,
+}
+
+impl Default for Layout {
+    fn default() -> Layout {
+        Layout {
+            columns: 64,
+            cell_size: 8,
+        }
+    }
+}
+
+type Colour = (u8, u8, u8);
+
+// binary PPM image with one colour per address
+fn ppm(colours: &[Colour], layout: &Layout) -> Vec<u8> {
+    let rows = colours.len().div_ceil(layout.columns).max(1);
+    let width = layout.columns * layout.cell_size;
+    let height = rows * layout.cell_size;
+    let mut image = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    for y in 0..height {
+        for x in 0..width {
+            let address = (y / layout.cell_size) * layout.columns + x / layout.cell_size;
+            let (r, g, b) = colours.get(address).copied().unwrap_or((0, 0, 0));
+            image.extend([r, g, b]);
+        }
+    }
+    image
+}
+
+// log scale so a handful of accesses still shows up next to hot loops
+fn intensity(count: u64, max: u64) -> u8 {
+    if count == 0 {
+        return 0;
+    }
+    (55.0 + 200.0 * (count as f64).ln_1p() / (max as f64).ln_1p()) as u8
+}
+
+// red for writes, green for executed words, blue for reads
+pub fn heatmap(coverage: &Coverage, layout: &Layout) -> Vec<u8> {
+    let max = |counts: &[u64]| counts.iter().copied().max().unwrap_or(0);
+    let (max_writes, max_fetched, max_reads) = (
+        max(&coverage.writes),
+        max(&coverage.fetched),
+        max(&coverage.reads),
+    );
+    let colours: Vec<Colour> = (0..coverage.fetched.len())
+        .map(|a| {
+            (
+                intensity(coverage.writes[a], max_writes),
+                intensity(coverage.fetched[a], max_fetched),
+                intensity(coverage.reads[a], max_reads),
+            )
+        })
+        .collect();
+    ppm(&colours, layout)
+}
+
+pub struct Frame {
+    pub memory: Vec<i64>,
+    // addresses written since the previous frame
+    pub written: Vec<bool>,
+    pub position: usize,
+    pub step: u64,
+}
+
+// snapshots memory every `interval` instructions, to be played back as an animation
+pub struct FrameRecorder {
+    pub frames: Vec<Frame>,
+    memory: Vec<i64>,
+    written: Vec<bool>,
+    interval: u64,
+    steps: u64,
+    position: usize,
+}
+
+impl FrameRecorder {
+    pub fn new(program: &[i64], interval: u64) -> FrameRecorder {
+        FrameRecorder {
+            frames: vec![],
+            memory: program.to_vec(),
+            written: vec![false; program.len()],
+            interval: interval.max(1),
+            steps: 0,
+            position: 0,
+        }
+    }
+
+    // records the current state as a frame, e.g. once the machine halted
+    pub fn snapshot(&mut self) {
+        self.frames.push(Frame {
+            memory: self.memory.clone(),
+            written: self.written.clone(),
+            position: self.position,
+            step: self.steps,
+        });
+        self.written.iter_mut().for_each(|w| *w = false);
+    }
+
+    fn grow(&mut self, address: usize) {
+        if address >= self.memory.len() {
+            self.memory.resize(address + 1, 0);
+            self.written.resize(address + 1, false);
+        }
+    }
+
+    // zero is black, other values are shaded by magnitude, fresh writes are red
+    // and the instruction pointer is green
+    pub fn render(&self, frame: &Frame, layout: &Layout) -> Vec<u8> {
+        let len = self
+            .frames
+            .iter()
+            .map(|f| f.memory.len())
+            .max()
+            .unwrap_or(0);
+        let colours: Vec<Colour> = (0..len)
+            .map(|a| {
+                let value = frame.memory.get(a).copied().unwrap_or(0);
+                if a == frame.position {
+                    (0, 255, 0)
+                } else if frame.written.get(a) == Some(&true) {
+                    (255, 40, 40)
+                } else if value == 0 {
+                    (0, 0, 0)
+                } else {
+                    let shade = (60.0 + 195.0 * (value.unsigned_abs() as f64).ln_1p() / 30.0)
+                        .min(255.0) as u8;
+                    (shade, shade, shade)
+                }
+            })
+            .collect();
+        ppm(&colours, layout)
+    }
+
+    // writes frame_00000.ppm, frame_00001.ppm, ... and returns how many were written
+    pub fn write_frames(&self, directory: &Path, layout: &Layout) -> Result<usize> {
+        fs::create_dir_all(directory)?;
+        for (i, frame) in self.frames.iter().enumerate() {
+            fs::write(
+                directory.join(format!("frame_{:05}.ppm", i)),
+                self.render(frame, layout),
+            )?;
+        }
+        Ok(self.frames.len())
+    }
+}
+
+impl Observer for FrameRecorder {
+    fn on_execute(&mut self, position: usize, _len: usize) {
+        self.position = position;
+        if self.steps.is_multiple_of(self.interval) {
+            self.snapshot();
+        }
+        self.steps += 1;
+    }
+
+    fn on_write(&mut self, address: usize, value: i64) {
+        self.grow(address);
+        self.memory[address] = value;
+        self.written[address] = true;
+    }
+}
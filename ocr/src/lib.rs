@@ -0,0 +1,213 @@
+// Reads capital letters drawn in the Advent block fonts, 4 pixels wide and 6 tall or 6 wide and
+// 10 tall. Glyphs are split at blank columns and compared with their blank edge columns trimmed,
+// so letters don't need to sit on a fixed grid; a letter touching the next one is split by
+// trying each known glyph as a prefix.
+
+use std::io::{Error, ErrorKind, Result};
+
+const SMALL_HEIGHT: usize = 6;
+const LARGE_HEIGHT: usize = 10;
+
+const SMALL_FONT: [(char, &str); 18] = [
+    ('A', ".##.\n#..#\n#..#\n####\n#..#\n#..#"),
+    ('B', "###.\n#..#\n###.\n#..#\n#..#\n###."),
+    ('C', ".##.\n#..#\n#...\n#...\n#..#\n.##."),
+    ('E', "####\n#...\n###.\n#...\n#...\n####"),
+    ('F', "####\n#...\n###.\n#...\n#...\n#..."),
+    ('G', ".##.\n#..#\n#...\n#.##\n#..#\n.###"),
+    ('H', "#..#\n#..#\n####\n#..#\n#..#\n#..#"),
+    ('I', ".###\n..#.\n..#.\n..#.\n..#.\n.###"),
+    ('J', "..##\n...#\n...#\n...#\n#..#\n.##."),
+    ('K', "#..#\n#.#.\n##..\n#.#.\n#.#.\n#..#"),
+    ('L', "#...\n#...\n#...\n#...\n#...\n####"),
+    ('O', ".##.\n#..#\n#..#\n#..#\n#..#\n.##."),
+    ('P', "###.\n#..#\n#..#\n###.\n#...\n#..."),
+    ('R', "###.\n#..#\n#..#\n###.\n#.#.\n#..#"),
+    ('S', ".###\n#...\n#...\n.##.\n...#\n###."),
+    ('U', "#..#\n#..#\n#..#\n#..#\n#..#\n.##."),
+    ('Y', "#...#\n#...#\n.#.#.\n..#..\n..#..\n..#.."),
+    ('Z', "####\n...#\n..#.\n.#..\n#...\n####"),
+];
+
+const LARGE_FONT: [(char, &str); 15] = [
+    (
+        'A',
+        "..##..\n.#..#.\n#....#\n#....#\n#....#\n######\n#....#\n#....#\n#....#\n#....#",
+    ),
+    (
+        'B',
+        "#####.\n#....#\n#....#\n#....#\n#####.\n#....#\n#....#\n#....#\n#....#\n#####.",
+    ),
+    (
+        'C',
+        ".####.\n#....#\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#....#\n.####.",
+    ),
+    (
+        'E',
+        "######\n#.....\n#.....\n#.....\n#####.\n#.....\n#.....\n#.....\n#.....\n######",
+    ),
+    (
+        'F',
+        "######\n#.....\n#.....\n#.....\n#####.\n#.....\n#.....\n#.....\n#.....\n#.....",
+    ),
+    (
+        'G',
+        ".####.\n#....#\n#.....\n#.....\n#.....\n#..###\n#....#\n#....#\n#...##\n.###.#",
+    ),
+    (
+        'H',
+        "#....#\n#....#\n#....#\n#....#\n######\n#....#\n#....#\n#....#\n#....#\n#....#",
+    ),
+    (
+        'J',
+        "...###\n....#.\n....#.\n....#.\n....#.\n....#.\n....#.\n#...#.\n#...#.\n.###..",
+    ),
+    (
+        'K',
+        "#....#\n#...#.\n#..#..\n#.#...\n##....\n##....\n#.#...\n#..#..\n#...#.\n#....#",
+    ),
+    (
+        'L',
+        "#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n#.....\n######",
+    ),
+    (
+        'N',
+        "#....#\n##...#\n##...#\n#.#..#\n#.#..#\n#..#.#\n#..#.#\n#...##\n#...##\n#....#",
+    ),
+    (
+        'P',
+        "#####.\n#....#\n#....#\n#....#\n#####.\n#.....\n#.....\n#.....\n#.....\n#.....",
+    ),
+    (
+        'R',
+        "#####.\n#....#\n#....#\n#....#\n#####.\n#..#..\n#...#.\n#...#.\n#....#\n#....#",
+    ),
+    (
+        'X',
+        "#....#\n#....#\n.#..#.\n.#..#.\n..##..\n..##..\n.#..#.\n.#..#.\n#....#\n#....#",
+    ),
+    (
+        'Z',
+        "######\n.....#\n.....#\n....#.\n...#..\n..#...\n.#....\n#.....\n#.....\n######",
+    ),
+];
+
+// glyph stored column by column, each column a bitmask of its rows
+type Columns = Vec<u32>;
+
+fn columns(grid: &[Vec<bool>]) -> Columns {
+    let width = grid.iter().map(|row| row.len()).max().unwrap_or(0);
+    (0..width)
+        .map(|x| {
+            grid.iter()
+                .enumerate()
+                .filter(|(_, row)| row.get(x).copied().unwrap_or(false))
+                .fold(0, |mask, (y, _)| mask | 1 << y)
+        })
+        .collect()
+}
+
+fn trim(columns: &[u32]) -> &[u32] {
+    let start = columns
+        .iter()
+        .position(|c| *c != 0)
+        .unwrap_or(columns.len());
+    let end = columns
+        .iter()
+        .rposition(|c| *c != 0)
+        .map_or(start, |e| e + 1);
+    &columns[start..end]
+}
+
+fn font(glyphs: &[(char, &str)]) -> Vec<(char, Columns)> {
+    glyphs
+        .iter()
+        .map(|(letter, glyph)| (*letter, trim(&columns(&parse_grid(glyph))).to_vec()))
+        .collect()
+}
+
+// text with `#` for set pixels into a grid, any other character is blank
+pub fn parse_grid(text: &str) -> Vec<Vec<bool>> {
+    text.lines()
+        .map(|line| line.chars().map(|c| c == '#').collect())
+        .collect()
+}
+
+fn dump(columns: &[u32], height: usize) -> String {
+    (0..height)
+        .map(|y| {
+            columns
+                .iter()
+                .map(|c| if c & 1 << y != 0 { '#' } else { '.' })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// letters of a run of columns with no blank column in it
+fn read_run(run: &[u32], font: &[(char, Columns)]) -> Option<String> {
+    if run.is_empty() {
+        return Some(String::new());
+    }
+    for (letter, glyph) in font {
+        if run.starts_with(glyph) {
+            if let Some(rest) = read_run(trim(&run[glyph.len()..]), font) {
+                return Some(format!("{}{}", letter, rest));
+            }
+        }
+    }
+    None
+}
+
+// decodes the letters in a grid, ignoring blank rows and columns around them
+pub fn recognise(grid: &[Vec<bool>]) -> Result<String> {
+    let top = grid.iter().position(|row| row.contains(&true));
+    let bottom = grid.iter().rposition(|row| row.contains(&true));
+    let (Some(top), Some(bottom)) = (top, bottom) else {
+        return Ok(String::new());
+    };
+    let height = bottom - top + 1;
+    let font = match height {
+        SMALL_HEIGHT => font(&SMALL_FONT),
+        LARGE_HEIGHT => font(&LARGE_FONT),
+        _ => {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "letters are {} pixels tall, expected {} or {}",
+                    height, SMALL_HEIGHT, LARGE_HEIGHT
+                ),
+            ))
+        }
+    };
+
+    let columns = columns(&grid[top..=bottom]);
+    let mut text = String::new();
+    let mut x = 0;
+    while x < columns.len() {
+        if columns[x] == 0 {
+            x += 1;
+            continue;
+        }
+        let end = columns[x..]
+            .iter()
+            .position(|c| *c == 0)
+            .map_or(columns.len(), |e| x + e);
+        match read_run(&columns[x..end], &font) {
+            Some(letters) => text += &letters,
+            None => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "unknown glyph at column {}:\n{}",
+                        x,
+                        dump(&columns[x..end], height)
+                    ),
+                ))
+            }
+        }
+        x = end;
+    }
+    Ok(text)
+}